@@ -0,0 +1,136 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// The solution to a puzzle part.
+///
+/// Numeric answers are stored as `Int` whenever they fit in an `i64` and as
+/// `BigInt` otherwise, but both compare, hash and print by their exact value.
+#[derive(Debug, Clone)]
+pub enum Answer {
+    Int(i64),
+    BigInt(i128),
+    Str(String),
+}
+
+impl Answer {
+    fn as_i128(&self) -> Option<i128> {
+        match self {
+            Answer::Int(n) => Some(*n as i128),
+            Answer::BigInt(n) => Some(*n),
+            Answer::Str(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Int(n) => write!(f, "{}", n),
+            Answer::BigInt(n) => write!(f, "{}", n),
+            Answer::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl PartialEq for Answer {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Answer {}
+
+impl PartialOrd for Answer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Numbers order before strings.
+impl Ord for Answer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Answer::Str(a), Answer::Str(b)) => a.cmp(b),
+            (Answer::Str(_), _) => Ordering::Greater,
+            (_, Answer::Str(_)) => Ordering::Less,
+            (a, b) => a.as_i128().cmp(&b.as_i128()),
+        }
+    }
+}
+
+impl Hash for Answer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Answer::Str(s) => s.hash(state),
+            n => n.as_i128().hash(state),
+        }
+    }
+}
+
+macro_rules! impl_from_small_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Int(n as i64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_small_int!(i8, u8, i16, u16, i32, u32, i64);
+
+macro_rules! impl_from_wide_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    let n = n as i128;
+                    if n >= i64::MIN as i128 && n <= i64::MAX as i128 {
+                        Answer::Int(n as i64)
+                    } else {
+                        Answer::BigInt(n)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_wide_int!(u64, usize, isize, i128);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Str(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Str(s.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting() {
+        assert_eq!(Answer::from(191164i32).to_string(), "191164");
+        assert_eq!(Answer::from(-3i64).to_string(), "-3");
+        assert_eq!(Answer::from(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(Answer::from("abc").to_string(), "abc");
+    }
+
+    #[test]
+    fn comparison() {
+        assert_eq!(Answer::Int(42), Answer::BigInt(42));
+        assert_eq!(Answer::from(42u16), Answer::from(42usize));
+        assert!(Answer::from(1i32) < Answer::from(u64::MAX));
+        assert!(Answer::from(u64::MAX) < Answer::from("0"));
+        assert_ne!(Answer::from(42i32), Answer::from("42"));
+    }
+}
@@ -1,40 +1,299 @@
+use std::convert::TryInto;
+use std::fmt;
+
+use aoc_2015::cli::Args;
 use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::Answer;
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const LOW_BITS: u64 = 0x7f7f_7f7f_7f7f_7f7f;
 
-fn solve_a(data: &String) -> i32 {
-    data.chars().fold(0, |lvl, c| match c {
-        '(' => lvl + 1,
-        ')' => lvl - 1,
-        _ => lvl,
-    })
+/// Counts the bytes in `word` that are equal to `byte`, eight at a time.
+fn count_in_word(word: u64, byte: u8) -> u32 {
+    let diff = word ^ (ONES * byte as u64);
+    let non_zero = ((diff & LOW_BITS) + LOW_BITS) | diff;
+    (!(non_zero | LOW_BITS)).count_ones()
 }
 
-fn solve_b(data: &String) -> i32 {
-    data.chars()
-        .enumerate()
-        .try_fold(0i32, |lvl, (idx, c)| {
-            Ok(match c {
-                '(' => lvl + 1,
-                ')' => lvl - 1,
-                _ => lvl,
-            })
-            .and_then(|lvl| {
-                if lvl == -1 {
-                    Err(idx as i32 + 1)
-                } else {
-                    Ok(lvl)
-                }
-            })
+/// Counts opening and closing parentheses a word at a time, so large inputs
+/// don't pay for a branch per byte.
+fn count_parens(data: &[u8]) -> (u64, u64) {
+    let chunks = data.chunks_exact(8);
+    let remainder = chunks.remainder();
+
+    let (up, down) = chunks
+        .map(|chunk| u64::from_ne_bytes(chunk.try_into().unwrap()))
+        .fold((0u64, 0u64), |(up, down), word| {
+            (
+                up + count_in_word(word, b'(') as u64,
+                down + count_in_word(word, b')') as u64,
+            )
+        });
+
+    remainder
+        .iter()
+        .fold((up, down), |(up, down), byte| match byte {
+            b'(' => (up + 1, down),
+            b')' => (up, down + 1),
+            _ => (up, down),
         })
-        .unwrap_err()
 }
 
-fn main() {
-    let lines = read_input();
-    let data = lines.first().unwrap();
+#[derive(Debug, PartialEq)]
+enum FloorError {
+    /// The 1-based positions of every byte other than `(` and `)`.
+    InvalidCharacters(Vec<usize>),
+    NeverReached(i64),
+}
+
+impl fmt::Display for FloorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FloorError::InvalidCharacters(positions) => write!(
+                f,
+                "Invalid characters at positions {}",
+                positions
+                    .iter()
+                    .map(|position| position.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            FloorError::NeverReached(-1) => write!(f, "Never entered the basement"),
+            FloorError::NeverReached(target) => write!(f, "Never reached floor {}", target),
+        }
+    }
+}
+
+/// Rejects any byte that is not a parenthesis. Valid input is confirmed with
+/// the fast counter, so only invalid input is scanned byte by byte.
+fn validate(data: &[u8]) -> Result<(), FloorError> {
+    let (up, down) = count_parens(data);
+    if (up + down) as usize == data.len() {
+        return Ok(());
+    }
+
+    Err(FloorError::InvalidCharacters(
+        data.iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte != b'(' && **byte != b')')
+            .map(|(idx, _)| idx + 1)
+            .collect(),
+    ))
+}
+
+fn step(floor: i64, byte: u8) -> i64 {
+    match byte {
+        b'(' => floor + 1,
+        b')' => floor - 1,
+        _ => floor,
+    }
+}
+
+/// The floor after every instruction, with the instruction's 1-based
+/// position.
+fn trajectory(data: &[u8]) -> impl Iterator<Item = (usize, i64)> + '_ {
+    data.iter()
+        .scan(0i64, |floor, byte| {
+            *floor = step(*floor, *byte);
+            Some(*floor)
+        })
+        .enumerate()
+        .map(|(idx, floor)| (idx + 1, floor))
+}
+
+/// Returns the (1-based) position of the first instruction that reaches
+/// `target`.
+fn first_entry(data: &[u8], target: i64) -> Option<usize> {
+    trajectory(data)
+        .find(|(_, floor)| *floor == target)
+        .map(|(position, _)| position)
+}
+
+#[derive(Debug, PartialEq)]
+struct FloorReport {
+    final_floor: i64,
+    target: i64,
+    first_entry: Option<usize>,
+    entries: Vec<usize>,
+    /// The highest floor and the first position it was reached at.
+    max: (i64, usize),
+    /// The lowest floor and the first position it was reached at.
+    min: (i64, usize),
+}
+
+/// Follows the whole trajectory. Positions are 1-based like in the puzzle,
+/// with position 0 being the starting floor.
+fn report(data: &[u8], target: i64) -> FloorReport {
+    let mut report = FloorReport {
+        final_floor: 0,
+        target,
+        first_entry: first_entry(data, target),
+        entries: Vec::new(),
+        max: (0, 0),
+        min: (0, 0),
+    };
+
+    for (position, floor) in trajectory(data) {
+        if floor == target && report.final_floor != target {
+            report.entries.push(position);
+        }
+        if floor > report.max.0 {
+            report.max = (floor, position);
+        }
+        if floor < report.min.0 {
+            report.min = (floor, position);
+        }
+
+        report.final_floor = floor;
+    }
+
+    report
+}
+
+impl fmt::Display for FloorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Final floor: {}", self.final_floor)?;
+        writeln!(
+            f,
+            "Highest floor: {} at position {}",
+            self.max.0, self.max.1
+        )?;
+        writeln!(f, "Lowest floor: {} at position {}", self.min.0, self.min.1)?;
+        match self.first_entry {
+            Some(position) => write!(
+                f,
+                "Floor {}: first reached at position {}, entered {} times",
+                self.target,
+                position,
+                self.entries.len()
+            ),
+            None => write!(f, "Floor {}: never reached", self.target),
+        }
+    }
+}
+
+fn solve_a(data: &str, strict: bool) -> Result<Answer, FloorError> {
+    if strict {
+        validate(data.as_bytes())?;
+    }
+
+    let (up, down) = count_parens(data.as_bytes());
+    Ok((up as i64 - down as i64).into())
+}
+
+fn solve_b(data: &str, target: i64, strict: bool) -> Result<Answer, FloorError> {
+    if strict {
+        validate(data.as_bytes())?;
+    }
+
+    first_entry(data.as_bytes(), target)
+        .map(Answer::from)
+        .ok_or(FloorError::NeverReached(target))
+}
+
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+    let target = match args.value("target") {
+        Some(target) => target
+            .parse()
+            .map_err(|_| format!("Invalid target floor '{}'", target))?,
+        None => -1,
+    };
+    let strict = args.flag("strict");
+
+    let lines = logging::read(read_input);
+    let data = lines.first().ok_or("Error reading input")?;
+
+    let solution_a = logging::solve("a", || solve_a(data, strict));
+    let solution_b = logging::solve("b", || solve_b(data, target, strict));
+
+    // The report is printed before any error is returned, since it also
+    // covers a target floor that is never reached.
+    for answer in [&solution_a, &solution_b].iter().copied().flatten() {
+        println!("{}", answer);
+    }
+
+    if args.flag("report") {
+        println!("{}", report(data.as_bytes(), target));
+    }
+
+    solution_a.map_err(|e| e.to_string())?;
+    solution_b.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count() {
+        assert_eq!(count_parens(b"(())"), (2, 2));
+        assert_eq!(count_parens(b"))((((("), (5, 2));
+        assert_eq!(count_parens(b"((x)(()()))(()\n(()"), (9, 7));
+        assert_eq!(count_parens(&[b'('; 1001]), (1001, 0));
+    }
+
+    #[test]
+    fn floors() {
+        assert_eq!(solve_a("(()(()(", true), Ok(Answer::from(3)));
+        assert_eq!(solve_a(")())())", true), Ok(Answer::from(-3)));
+        assert_eq!(solve_b(")", -1, true), Ok(Answer::from(1)));
+        assert_eq!(solve_b("()())", -1, true), Ok(Answer::from(5)));
+        assert_eq!(solve_b("(((", 2, true), Ok(Answer::from(2)));
+    }
+
+    #[test]
+    fn never_reached() {
+        assert_eq!(solve_b("(()", -1, true), Err(FloorError::NeverReached(-1)));
+        assert_eq!(
+            FloorError::NeverReached(-1).to_string(),
+            "Never entered the basement"
+        );
+    }
 
-    let solution_a = solve_a(data);
-    let solution_b = solve_b(data);
+    #[test]
+    fn invalid_characters() {
+        assert_eq!(solve_a("(a)) (", false), Ok(Answer::from(0)));
+        assert_eq!(
+            solve_a("(a)) (", true),
+            Err(FloorError::InvalidCharacters(vec![2, 5]))
+        );
+        assert_eq!(
+            FloorError::InvalidCharacters(vec![2, 5]).to_string(),
+            "Invalid characters at positions 2, 5"
+        );
+    }
 
-    println!("{}", solution_a);
-    println!("{}", solution_b);
+    #[test]
+    fn trajectory() {
+        assert_eq!(
+            report(b"()())(()))", -1),
+            FloorReport {
+                final_floor: -2,
+                target: -1,
+                first_entry: Some(5),
+                entries: vec![5, 9],
+                max: (1, 1),
+                min: (-2, 10),
+            }
+        );
+        assert_eq!(
+            report(b"()())(()))", -1).to_string(),
+            "Final floor: -2\n\
+             Highest floor: 1 at position 1\n\
+             Lowest floor: -2 at position 10\n\
+             Floor -1: first reached at position 5, entered 2 times"
+        );
+        assert_eq!(
+            report(b"((", -1).to_string(),
+            "Final floor: 2\n\
+             Highest floor: 2 at position 2\n\
+             Lowest floor: 0 at position 0\n\
+             Floor -1: never reached"
+        );
+    }
 }
@@ -0,0 +1,349 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+use aoc_2015::cli::Args;
+use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::Answer;
+
+mod packing;
+mod stats;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Present {
+    l: u32,
+    w: u32,
+    h: u32,
+}
+
+/// Points at the offending token. Line and column are 1-based; `FromStr`
+/// reports line 1 and `parse_presents` fills in the real line number.
+#[derive(Debug, PartialEq)]
+struct ParsePresentError {
+    line: usize,
+    column: usize,
+    token: String,
+}
+
+impl fmt::Display for ParsePresentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.token.is_empty() {
+            write!(
+                f,
+                "line {}, column {}: missing dimension",
+                self.line, self.column
+            )
+        } else {
+            write!(
+                f,
+                "line {}, column {}: invalid dimension '{}'",
+                self.line, self.column, self.token
+            )
+        }
+    }
+}
+
+impl Present {
+    fn smallest_sides(self: &Self) -> (u32, u32) {
+        let mut sides = [self.l, self.w, self.h];
+        sides.sort();
+        (sides[0], sides[1])
+    }
+
+    fn dimensions(&self) -> (u128, u128, u128) {
+        (self.l as u128, self.w as u128, self.h as u128)
+    }
+
+    /// The extra paper, equal to the area of the smallest side.
+    fn slack(&self) -> u128 {
+        let (small_1, small_2) = self.smallest_sides();
+
+        small_1 as u128 * small_2 as u128
+    }
+
+    /// The ribbon for the bow, equal to the volume of the present.
+    fn bow(&self) -> u128 {
+        let (l, w, h) = self.dimensions();
+
+        l * w * h
+    }
+
+    /// Computed in `u128`, which can't overflow for any `u32` dimensions.
+    fn surface(self: &Self) -> u128 {
+        let (l, w, h) = self.dimensions();
+
+        2 * l * w + 2 * w * h + 2 * l * h + self.slack()
+    }
+
+    /// Computed in `u128`, which can't overflow for any `u32` dimensions.
+    fn ribbon(self: &Self) -> u128 {
+        let (small_1, small_2) = self.smallest_sides();
+
+        2 * small_1 as u128 + 2 * small_2 as u128 + self.bow()
+    }
+}
+
+impl FromStr for Present {
+    type Err = ParsePresentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut dimensions = [0u32; 3];
+        let mut column = 1;
+        let mut tokens = s.split('x');
+
+        for dimension in dimensions.iter_mut() {
+            let token = tokens.next().unwrap_or("");
+            *dimension = token.parse().map_err(|_| ParsePresentError {
+                line: 1,
+                // A missing last dimension points at the end of the line.
+                column: column.min(s.len().max(1)),
+                token: token.to_string(),
+            })?;
+            column += token.len() + 1;
+        }
+
+        if let Some(token) = tokens.next() {
+            return Err(ParsePresentError {
+                line: 1,
+                column,
+                token: token.to_string(),
+            });
+        }
+
+        let [l, w, h] = dimensions;
+        Ok(Present { l, w, h })
+    }
+}
+
+fn parse_lines(data: &[String]) -> impl Iterator<Item = Result<Present, ParsePresentError>> + '_ {
+    data.iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            line.parse()
+                .map_err(|e| ParsePresentError { line: idx + 1, ..e })
+        })
+}
+
+/// Parses every line, failing on the first line that isn't a valid present.
+/// Blank lines are ignored.
+fn parse_presents(data: &[String]) -> Result<Vec<Present>, ParsePresentError> {
+    parse_lines(data).collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct OverflowError;
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "total does not fit in 128 bits")
+    }
+}
+
+fn checked_total(values: impl IntoIterator<Item = u128>) -> Result<Answer, OverflowError> {
+    let total = values.into_iter().try_fold(0u128, |total, value| {
+        total.checked_add(value).ok_or(OverflowError)
+    })?;
+
+    i128::try_from(total)
+        .map(Answer::from)
+        .map_err(|_| OverflowError)
+}
+
+#[derive(Debug, PartialEq, Default)]
+struct ParseReport {
+    presents: Vec<Present>,
+    skipped: Vec<ParsePresentError>,
+}
+
+/// Parses every valid line and keeps track of the lines that were skipped.
+fn parse_presents_lenient(data: &[String]) -> ParseReport {
+    parse_lines(data).fold(ParseReport::default(), |mut report, result| {
+        match result {
+            Ok(present) => report.presents.push(present),
+            Err(error) => report.skipped.push(error),
+        }
+        report
+    })
+}
+
+fn solve_a(presents: &[Present]) -> Result<Answer, OverflowError> {
+    checked_total(presents.iter().map(|present| present.surface()))
+}
+
+fn solve_b(presents: &[Present]) -> Result<Answer, OverflowError> {
+    checked_total(presents.iter().map(|present| present.ribbon()))
+}
+
+/// Parses the size of a container, whose dimensions must all be positive.
+fn parse_container(s: &str) -> Result<Present, String> {
+    let bounds: Present = s
+        .parse()
+        .map_err(|e: ParsePresentError| format!("Invalid container size: {}", e))?;
+
+    if [bounds.l, bounds.w, bounds.h].contains(&0) {
+        Err(format!(
+            "Invalid container size '{}': dimensions must be positive",
+            s
+        ))
+    } else {
+        Ok(bounds)
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+
+    let lines = logging::read(read_input);
+    let presents = if args.flag("lenient") {
+        let report = logging::parse(|| parse_presents_lenient(&lines));
+        for error in &report.skipped {
+            eprintln!("Skipped {}", error);
+        }
+        report.presents
+    } else {
+        logging::parse(|| parse_presents(&lines)).map_err(|e| e.to_string())?
+    };
+
+    let solution_a = logging::solve("a", || solve_a(&presents));
+    let solution_b = logging::solve("b", || solve_b(&presents));
+
+    println!("{}", solution_a.map_err(|e| e.to_string())?);
+    println!("{}", solution_b.map_err(|e| e.to_string())?);
+
+    if let Some(container) = args.value("pack") {
+        let bounds = parse_container(container)?;
+        let packing = packing::pack(&bounds, &presents).map_err(|e| e.to_string())?;
+        print!("{}", packing);
+    }
+
+    if args.flag("stats") {
+        print!(
+            "{}",
+            stats::Statistics::new(&presents).map_err(|e| e.to_string())?
+        );
+    }
+
+    if let Some(format) = args.value("export") {
+        let export = match format {
+            "csv" => stats::to_csv(&presents),
+            "json" => stats::to_json(&presents).map_err(|e| e.to_string())?,
+            _ => return Err(format!("Unknown export format '{}'", format)),
+        };
+
+        match args.value("output") {
+            Some(path) => fs::write(path, export).map_err(|e| e.to_string())?,
+            None => print!("{}", export),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parser() {
+        assert_eq!("3x4x5".parse(), Ok(Present { l: 3, w: 4, h: 5 }))
+    }
+
+    #[test]
+    fn parser_errors() {
+        assert_eq!(
+            "3x4yx5".parse::<Present>(),
+            Err(ParsePresentError {
+                line: 1,
+                column: 3,
+                token: "4y".to_string()
+            })
+        );
+        assert_eq!(
+            "3x4".parse::<Present>().unwrap_err().to_string(),
+            "line 1, column 3: missing dimension"
+        );
+        assert_eq!(
+            "3x4x".parse::<Present>().unwrap_err().to_string(),
+            "line 1, column 4: missing dimension"
+        );
+        assert_eq!(
+            "3xx4".parse::<Present>().unwrap_err().to_string(),
+            "line 1, column 3: missing dimension"
+        );
+        assert_eq!(
+            "3x4x5x6".parse::<Present>().unwrap_err().to_string(),
+            "line 1, column 7: invalid dimension '6'"
+        );
+    }
+
+    #[test]
+    fn containers() {
+        assert_eq!(parse_container("2x3x4"), Ok(Present { l: 2, w: 3, h: 4 }));
+        assert_eq!(
+            parse_container("0x1x1"),
+            Err("Invalid container size '0x1x1': dimensions must be positive".to_string())
+        );
+        assert!(parse_container("2x3").is_err());
+    }
+
+    #[test]
+    fn strict_and_lenient() {
+        let lines: Vec<String> = vec!["2x3x4", "", "1x1x1O", "1x1x10"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            parse_presents(&lines).unwrap_err().to_string(),
+            "line 3, column 5: invalid dimension '1O'"
+        );
+
+        let report = parse_presents_lenient(&lines);
+        assert_eq!(report.presents.len(), 2);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].line, 3);
+    }
+
+    #[test]
+    fn surface() {
+        assert_eq!(Present { l: 3, w: 4, h: 2 }.surface(), 58);
+        assert_eq!(Present { l: 1, w: 1, h: 10 }.surface(), 43);
+    }
+
+    #[test]
+    fn ribbon() {
+        assert_eq!(Present { l: 3, w: 4, h: 2 }.ribbon(), 34);
+        assert_eq!(Present { l: 1, w: 1, h: 10 }.ribbon(), 14);
+    }
+
+    #[test]
+    fn wide_totals() {
+        let huge = Present {
+            l: u32::MAX,
+            w: u32::MAX,
+            h: u32::MAX,
+        };
+        let max = u32::MAX as u128;
+
+        assert_eq!(huge.surface(), 7 * max * max);
+        assert_eq!(huge.ribbon(), 4 * max + max * max * max);
+        assert_eq!(
+            solve_b(&[huge]),
+            Ok(Answer::from((4 * max + max * max * max) as i128))
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(checked_total(vec![u128::MAX, 1]), Err(OverflowError));
+        assert_eq!(
+            checked_total(vec![i128::MAX as u128, 1]),
+            Err(OverflowError)
+        );
+        assert_eq!(checked_total(vec![1, 2]), Ok(Answer::from(3)));
+    }
+}
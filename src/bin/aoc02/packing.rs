@@ -0,0 +1,379 @@
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::Present;
+
+type Point = (u32, u32, u32);
+type Size = (u32, u32, u32);
+
+/// Where a present ended up inside its container, after rotating it to `size`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Placement {
+    pub present: usize,
+    pub position: Point,
+    pub size: Size,
+}
+
+impl Placement {
+    fn overlaps(&self, position: Point, size: Size) -> bool {
+        let (x, y, z) = self.position;
+        let (l, w, h) = self.size;
+
+        x < position.0 + size.0
+            && position.0 < x + l
+            && y < position.1 + size.1
+            && position.1 < y + w
+            && z < position.2 + size.2
+            && position.2 < z + h
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Container {
+    pub placements: Vec<Placement>,
+    /// Candidate corners for the next present: the origin and the far
+    /// corners of every placed present along each axis.
+    extreme_points: Vec<Point>,
+}
+
+impl Container {
+    fn new() -> Self {
+        Container {
+            placements: Vec::new(),
+            extreme_points: vec![(0, 0, 0)],
+        }
+    }
+
+    fn fits(&self, bounds: &Present, position: Point, size: Size) -> bool {
+        position.0 as u64 + size.0 as u64 <= bounds.l as u64
+            && position.1 as u64 + size.1 as u64 <= bounds.w as u64
+            && position.2 as u64 + size.2 as u64 <= bounds.h as u64
+            && self
+                .placements
+                .iter()
+                .all(|placement| !placement.overlaps(position, size))
+    }
+
+    /// Every way `present` can be put into this container.
+    fn candidates(&self, bounds: &Present, present: &Present) -> Vec<(Point, Size)> {
+        let mut candidates: Vec<(Point, Size)> = self
+            .extreme_points
+            .iter()
+            .flat_map(|point| {
+                rotations(present)
+                    .into_iter()
+                    .map(move |size| (*point, size))
+            })
+            .filter(|(point, size)| self.fits(bounds, *point, *size))
+            .collect();
+
+        candidates.sort_by_key(|((x, y, z), size)| (*z, *y, *x, *size));
+        candidates.dedup();
+        candidates
+    }
+
+    fn place(&mut self, present: usize, position: Point, size: Size) {
+        let (x, y, z) = position;
+        let (l, w, h) = size;
+
+        self.extreme_points.retain(|point| *point != position);
+        self.extreme_points
+            .extend(vec![(x + l, y, z), (x, y + w, z), (x, y, z + h)]);
+        self.placements.push(Placement {
+            present,
+            position,
+            size,
+        });
+    }
+
+    fn used_volume(&self) -> u128 {
+        self.placements
+            .iter()
+            .map(|placement| volume(placement.size))
+            .sum()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Packing {
+    pub bounds: Present,
+    pub containers: Vec<Container>,
+}
+
+impl Packing {
+    /// The fraction of each container's volume that is filled with presents.
+    pub fn fill_ratios(&self) -> Vec<f64> {
+        let capacity = self.bounds.volume() as f64;
+
+        self.containers
+            .iter()
+            .map(|container| container.used_volume() as f64 / capacity)
+            .collect()
+    }
+}
+
+impl fmt::Display for Packing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (container, ratio)) in self.containers.iter().zip(self.fill_ratios()).enumerate()
+        {
+            writeln!(
+                f,
+                "container {}: {} presents, {:.1}% full",
+                idx + 1,
+                container.placements.len(),
+                ratio * 100.0
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TooLargeError {
+    pub present: usize,
+}
+
+impl fmt::Display for TooLargeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "present {} does not fit in the container in any orientation",
+            self.present + 1
+        )
+    }
+}
+
+/// Up to this many presents `pack` uses the exact solver.
+pub const EXACT_LIMIT: usize = 8;
+
+/// In `u128`, like the surface and ribbon, so that no three `u32` sides can
+/// overflow it.
+fn volume((l, w, h): Size) -> u128 {
+    l as u128 * w as u128 * h as u128
+}
+
+impl Present {
+    fn volume(&self) -> u128 {
+        volume((self.l, self.w, self.h))
+    }
+}
+
+fn rotations(present: &Present) -> Vec<Size> {
+    let Present { l, w, h } = *present;
+    let mut rotations = vec![
+        (l, w, h),
+        (l, h, w),
+        (w, l, h),
+        (w, h, l),
+        (h, l, w),
+        (h, w, l),
+    ];
+    rotations.sort();
+    rotations.dedup();
+    rotations
+}
+
+fn check_sizes(bounds: &Present, presents: &[Present]) -> Result<(), TooLargeError> {
+    let empty = Container::new();
+
+    match presents
+        .iter()
+        .position(|present| empty.candidates(bounds, present).is_empty())
+    {
+        Some(present) => Err(TooLargeError { present }),
+        None => Ok(()),
+    }
+}
+
+/// Indices of the presents, largest volume first.
+fn decreasing(presents: &[Present]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..presents.len()).collect();
+    order.sort_by_key(|idx| Reverse(presents[*idx].volume()));
+    order
+}
+
+/// Puts every present, largest first, into the first container it fits in
+/// and only opens a new container when none has room.
+pub fn first_fit_decreasing(
+    bounds: &Present,
+    presents: &[Present],
+) -> Result<Packing, TooLargeError> {
+    check_sizes(bounds, presents)?;
+
+    let mut containers: Vec<Container> = Vec::new();
+    for idx in decreasing(presents) {
+        let slot = containers.iter().enumerate().find_map(|(c, container)| {
+            container
+                .candidates(bounds, &presents[idx])
+                .first()
+                .map(|(position, size)| (c, *position, *size))
+        });
+
+        match slot {
+            Some((c, position, size)) => containers[c].place(idx, position, size),
+            None => {
+                let mut container = Container::new();
+                let (position, size) = container.candidates(bounds, &presents[idx])[0];
+                container.place(idx, position, size);
+                containers.push(container);
+            }
+        }
+    }
+
+    Ok(Packing {
+        bounds: *bounds,
+        containers,
+    })
+}
+
+/// Finds the fewest containers by trying every container, corner and
+/// rotation for every present. The result is optimal among the packings that
+/// extreme point placement can build, which is exponential in the number of
+/// presents, so it is only meant for small instances.
+pub fn exact(bounds: &Present, presents: &[Present]) -> Result<Packing, TooLargeError> {
+    let mut best = first_fit_decreasing(bounds, presents)?.containers;
+
+    let capacity = bounds.volume();
+    let total: u128 = presents.iter().map(Present::volume).sum();
+    let lower_bound = total.div_ceil(capacity) as usize;
+
+    search(
+        bounds,
+        presents,
+        &decreasing(presents),
+        lower_bound,
+        &mut Vec::new(),
+        &mut best,
+    );
+
+    Ok(Packing {
+        bounds: *bounds,
+        containers: best,
+    })
+}
+
+fn search(
+    bounds: &Present,
+    presents: &[Present],
+    order: &[usize],
+    lower_bound: usize,
+    current: &mut Vec<Container>,
+    best: &mut Vec<Container>,
+) {
+    if current.len() >= best.len() || best.len() <= lower_bound {
+        return;
+    }
+
+    let (idx, rest) = match order.split_first() {
+        Some((idx, rest)) => (*idx, rest),
+        None => {
+            *best = current.clone();
+            return;
+        }
+    };
+
+    for c in 0..current.len() {
+        for (position, size) in current[c].candidates(bounds, &presents[idx]) {
+            let saved = current[c].clone();
+            current[c].place(idx, position, size);
+            search(bounds, presents, rest, lower_bound, current, best);
+            current[c] = saved;
+        }
+    }
+
+    if current.len() + 1 < best.len() {
+        let mut container = Container::new();
+        let (position, size) = container.candidates(bounds, &presents[idx])[0];
+        container.place(idx, position, size);
+
+        current.push(container);
+        search(bounds, presents, rest, lower_bound, current, best);
+        current.pop();
+    }
+}
+
+/// Uses the exact solver for small instances and first-fit decreasing for
+/// everything else.
+pub fn pack(bounds: &Present, presents: &[Present]) -> Result<Packing, TooLargeError> {
+    if presents.len() <= EXACT_LIMIT {
+        exact(bounds, presents)
+    } else {
+        first_fit_decreasing(bounds, presents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn present(l: u32, w: u32, h: u32) -> Present {
+        Present { l, w, h }
+    }
+
+    #[test]
+    fn rotates_to_fit() {
+        let packing = first_fit_decreasing(&present(1, 2, 3), &[present(3, 2, 1)]).unwrap();
+
+        assert_eq!(packing.containers.len(), 1);
+        assert_eq!(packing.containers[0].placements[0].size, (1, 2, 3));
+        assert_eq!(packing.fill_ratios(), vec![1.0]);
+    }
+
+    #[test]
+    fn too_large() {
+        assert_eq!(
+            pack(&present(2, 2, 2), &[present(1, 1, 1), present(3, 1, 1)]),
+            Err(TooLargeError { present: 1 })
+        );
+    }
+
+    #[test]
+    fn fills_containers() {
+        let presents = vec![present(1, 1, 1); 9];
+        let packing = first_fit_decreasing(&present(2, 2, 2), &presents).unwrap();
+
+        assert_eq!(packing.containers.len(), 2);
+        assert_eq!(packing.fill_ratios(), vec![1.0, 0.125]);
+    }
+
+    #[test]
+    fn large_containers() {
+        for bounds in &[
+            present(3_000_000, 3_000_000, 3_000_000),
+            present(1 << 22, 1 << 21, 1 << 21),
+            present(u32::MAX, u32::MAX, u32::MAX),
+        ] {
+            let packing = pack(bounds, &[present(1 << 20, 1 << 20, 1 << 20)]).unwrap();
+
+            assert_eq!(packing.containers.len(), 1);
+            assert_eq!(
+                packing.fill_ratios(),
+                vec![(1u128 << 60) as f64 / bounds.volume() as f64]
+            );
+        }
+    }
+
+    #[test]
+    fn exact_beats_first_fit() {
+        let bounds = present(5, 4, 3);
+        let presents = vec![
+            present(2, 2, 1),
+            present(2, 3, 1),
+            present(1, 3, 1),
+            present(4, 1, 1),
+            present(3, 1, 3),
+            present(4, 2, 3),
+            present(3, 2, 1),
+        ];
+
+        assert_eq!(
+            first_fit_decreasing(&bounds, &presents)
+                .unwrap()
+                .containers
+                .len(),
+            2
+        );
+        assert_eq!(exact(&bounds, &presents).unwrap().containers.len(), 1);
+    }
+}
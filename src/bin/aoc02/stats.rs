@@ -0,0 +1,215 @@
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::{OverflowError, Present};
+
+/// How many of the largest consumers are listed per measure.
+const TOP: usize = 5;
+
+/// Summary of one measure over all presents.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub total: u128,
+    pub mean: f64,
+    pub median: f64,
+    pub p90: u128,
+    pub p99: u128,
+    pub max: u128,
+    /// 1-based indices of the presents with the highest values, largest first.
+    pub largest: Vec<usize>,
+}
+
+impl Summary {
+    fn new(values: &[u128]) -> Result<Self, OverflowError> {
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable();
+
+        let total = values.iter().try_fold(0u128, |total, value| {
+            total.checked_add(*value).ok_or(OverflowError)
+        })?;
+        let median = match sorted.len() {
+            0 => 0.0,
+            n if n % 2 == 1 => sorted[n / 2] as f64,
+            n => (sorted[n / 2 - 1] as f64 + sorted[n / 2] as f64) / 2.0,
+        };
+
+        let mut largest: Vec<usize> = (0..values.len()).collect();
+        largest.sort_by_key(|idx| (Reverse(values[*idx]), *idx));
+        largest.truncate(TOP);
+
+        Ok(Summary {
+            total,
+            mean: if values.is_empty() {
+                0.0
+            } else {
+                total as f64 / values.len() as f64
+            },
+            median,
+            p90: percentile(&sorted, 90),
+            p99: percentile(&sorted, 99),
+            max: sorted.last().copied().unwrap_or(0),
+            largest: largest.into_iter().map(|idx| idx + 1).collect(),
+        })
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"total\":{},\"mean\":{},\"median\":{},\"p90\":{},\"p99\":{},\"max\":{},\"largest\":[{}]}}",
+            self.total,
+            self.mean,
+            self.median,
+            self.p90,
+            self.p99,
+            self.max,
+            self.largest
+                .iter()
+                .map(|idx| idx.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+}
+
+/// Nearest-rank percentile of already sorted values.
+fn percentile(sorted: &[u128], p: usize) -> u128 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Statistics {
+    pub count: usize,
+    pub paper: Summary,
+    pub ribbon: Summary,
+}
+
+impl Statistics {
+    pub fn new(presents: &[Present]) -> Result<Self, OverflowError> {
+        let paper: Vec<u128> = presents.iter().map(Present::surface).collect();
+        let ribbon: Vec<u128> = presents.iter().map(Present::ribbon).collect();
+
+        Ok(Statistics {
+            count: presents.len(),
+            paper: Summary::new(&paper)?,
+            ribbon: Summary::new(&ribbon)?,
+        })
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "presents: {}", self.count)?;
+
+        for (name, summary) in [("paper", &self.paper), ("ribbon", &self.ribbon)].iter() {
+            writeln!(
+                f,
+                "{}: total {}, mean {:.2}, median {}, p90 {}, p99 {}, max {}, largest {:?}",
+                name,
+                summary.total,
+                summary.mean,
+                summary.median,
+                summary.p90,
+                summary.p99,
+                summary.max,
+                summary.largest
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+const COLUMNS: [&str; 8] = ["present", "l", "w", "h", "paper", "slack", "ribbon", "bow"];
+
+fn rows(presents: &[Present]) -> impl Iterator<Item = [u128; 8]> + '_ {
+    presents.iter().enumerate().map(|(idx, present)| {
+        [
+            idx as u128 + 1,
+            present.l as u128,
+            present.w as u128,
+            present.h as u128,
+            present.surface(),
+            present.slack(),
+            present.ribbon(),
+            present.bow(),
+        ]
+    })
+}
+
+/// One row per present with its dimensions and wrapping requirements.
+pub fn to_csv(presents: &[Present]) -> String {
+    let header = COLUMNS.join(",");
+
+    rows(presents).fold(header + "\n", |mut csv, row| {
+        let fields: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+        csv
+    })
+}
+
+/// The per-present rows of `to_csv` together with the aggregate statistics.
+pub fn to_json(presents: &[Present]) -> Result<String, OverflowError> {
+    let rows: Vec<String> = rows(presents)
+        .map(|row| {
+            let fields: Vec<String> = COLUMNS
+                .iter()
+                .zip(row.iter())
+                .map(|(name, value)| format!("\"{}\":{}", name, value))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        })
+        .collect();
+    let statistics = Statistics::new(presents)?;
+
+    Ok(format!(
+        "{{\"presents\":[{}],\"statistics\":{{\"count\":{},\"paper\":{},\"ribbon\":{}}}}}\n",
+        rows.join(","),
+        statistics.count,
+        statistics.paper.to_json(),
+        statistics.ribbon.to_json()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presents() -> Vec<Present> {
+        vec![Present { l: 2, w: 3, h: 4 }, Present { l: 1, w: 1, h: 10 }]
+    }
+
+    #[test]
+    fn summary() {
+        let summary = Summary::new(&[5, 1, 4, 2, 3]).unwrap();
+
+        assert_eq!(summary.total, 15);
+        assert_eq!(summary.mean, 3.0);
+        assert_eq!(summary.median, 3.0);
+        assert_eq!(summary.p90, 5);
+        assert_eq!(summary.largest, vec![1, 3, 5, 4, 2]);
+        assert_eq!(Summary::new(&[u128::MAX, 1]), Err(OverflowError));
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            to_csv(&presents()),
+            "present,l,w,h,paper,slack,ribbon,bow\n1,2,3,4,58,6,34,24\n2,1,1,10,43,1,14,10\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let json = to_json(&presents()).unwrap();
+
+        assert!(json.starts_with(
+            "{\"presents\":[{\"present\":1,\"l\":2,\"w\":3,\"h\":4,\"paper\":58,\"slack\":6,"
+        ));
+        assert!(json.contains("\"paper\":{\"total\":101,\"mean\":50.5,\"median\":50.5,"));
+    }
+}
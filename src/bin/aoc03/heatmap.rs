@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use crate::{Delivery, Position};
+
+/// The largest image that is drawn, one pixel per house of the bounding box.
+const MAX_PIXELS: usize = 1 << 26;
+
+/// Colours for the workers' paths, reused when there are more workers.
+const PALETTE: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+#[derive(Debug, PartialEq)]
+struct Bounds {
+    min_x: i32,
+    max_x: i32,
+    min_y: i32,
+    max_y: i32,
+}
+
+impl Bounds {
+    fn new<'a>(positions: impl Iterator<Item = &'a Position>) -> Self {
+        positions.fold(
+            Bounds {
+                min_x: 0,
+                max_x: 0,
+                min_y: 0,
+                max_y: 0,
+            },
+            |bounds, Position(x, y)| Bounds {
+                min_x: bounds.min_x.min(*x),
+                max_x: bounds.max_x.max(*x),
+                min_y: bounds.min_y.min(*y),
+                max_y: bounds.max_y.max(*y),
+            },
+        )
+    }
+
+    fn width(&self) -> usize {
+        (self.max_x - self.min_x) as usize + 1
+    }
+
+    fn height(&self) -> usize {
+        (self.max_y - self.min_y) as usize + 1
+    }
+
+    /// Image coordinates of a house; north (`^`) is up.
+    fn pixel(&self, Position(x, y): Position) -> (usize, usize) {
+        ((x - self.min_x) as usize, (self.max_y - y) as usize)
+    }
+}
+
+/// Maps a visit count onto a black-red-yellow-white scale. The scale is
+/// logarithmic since a few houses get far more presents than the rest.
+fn heat(count: u32, max: u32) -> [u8; 3] {
+    if count == 0 {
+        return [0, 0, 0];
+    }
+
+    let t = if max <= 1 {
+        1.0
+    } else {
+        (count as f64).ln() / (max as f64).ln()
+    };
+    let level = |offset: f64| ((t * 3.0 - offset).clamp(0.0, 1.0) * 255.0) as u8;
+
+    [level(0.0).max(64), level(1.0), level(2.0)]
+}
+
+/// A binary PPM with one pixel per house, unless the route spans too many
+/// houses for that.
+pub fn to_ppm(visits: &HashMap<Position, u32>) -> Result<Vec<u8>, String> {
+    let bounds = Bounds::new(visits.keys());
+    let max = visits.values().copied().max().unwrap_or(0);
+    let (width, height) = (bounds.width(), bounds.height());
+    if width.saturating_mul(height) > MAX_PIXELS {
+        return Err(format!(
+            "The route spans {}x{} houses, which is too large for a heatmap",
+            width, height
+        ));
+    }
+
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    let header = image.len();
+    image.resize(header + width * height * 3, 0);
+
+    for (position, count) in visits {
+        let (x, y) = bounds.pixel(*position);
+        let offset = header + (y * width + x) * 3;
+        image[offset..offset + 3].copy_from_slice(&heat(*count, max));
+    }
+
+    Ok(image)
+}
+
+/// An SVG with the visit density as squares and every worker's path drawn on
+/// top in its own colour, for a delivery that recorded its paths.
+pub fn to_svg(delivery: &Delivery) -> String {
+    let bounds = Bounds::new(delivery.visits.keys());
+    let max = delivery.visits.values().copied().max().unwrap_or(0);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"black\"/>\n",
+        bounds.width(),
+        bounds.height()
+    );
+
+    let mut houses: Vec<(&Position, &u32)> = delivery.visits.iter().collect();
+    houses.sort_by_key(|(Position(x, y), _)| (*y, *x));
+    for (position, count) in houses {
+        let (x, y) = bounds.pixel(*position);
+        let [r, g, b] = heat(*count, max);
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" fill=\"rgb({},{},{})\"/>\n",
+            x, y, r, g, b
+        ));
+    }
+
+    for (worker, path) in delivery.paths.iter().enumerate() {
+        let points: Vec<String> = path
+            .iter()
+            .map(|position| {
+                let (x, y) = bounds.pixel(*position);
+                format!("{}.5,{}.5", x, y)
+            })
+            .collect();
+        svg.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"0.2\" stroke-opacity=\"0.7\"/>\n",
+            points.join(" "),
+            PALETTE[worker % PALETTE.len()]
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deliver;
+    use crate::moves::{Alphabet, Route};
+
+    fn moves(route: &str) -> Route {
+        Alphabet::default().parse(route).unwrap()
+    }
+
+    #[test]
+    fn ppm() {
+        let delivery = deliver(&moves("^^>"), 1, false);
+        let image = to_ppm(&delivery.visits).unwrap();
+        let header = b"P6\n2 3\n255\n";
+
+        assert_eq!(&image[..header.len()], header);
+        assert_eq!(image.len(), header.len() + 2 * 3 * 3);
+        // The top left pixel is house (0, 2), which was visited.
+        assert_ne!(&image[header.len()..header.len() + 3], &[0, 0, 0]);
+        // Nobody visited (1, 0) in the bottom right corner.
+        assert_eq!(&image[image.len() - 3..], &[0, 0, 0]);
+    }
+
+    #[test]
+    fn ppm_size() {
+        let delivery = deliver(
+            &moves(&format!("{}{}", "^".repeat(9000), ">".repeat(9000))),
+            1,
+            false,
+        );
+
+        assert!(to_ppm(&delivery.visits).is_err());
+    }
+
+    #[test]
+    fn svg() {
+        let svg = to_svg(&deliver(&moves("^>v<"), 2, true));
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains(PALETTE[1]));
+    }
+
+    #[test]
+    fn heat_scale() {
+        assert_eq!(heat(0, 10), [0, 0, 0]);
+        assert_eq!(heat(10, 10), [255, 255, 255]);
+        assert_eq!(heat(1, 10), [64, 0, 0]);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use crate::moves::Route;
+use crate::Position;
+
+/// A delivery to a house: the step it happened at and the worker that made it.
+/// Step 0 is the initial delivery to the origin, step `t` is the `t`-th move.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Visit {
+    pub step: usize,
+    pub worker: usize,
+}
+
+/// Indexes a delivery route so it can be replayed without walking the moves
+/// again. Every query is a lookup in a precomputed table.
+#[derive(Debug)]
+pub struct History {
+    workers: usize,
+    /// Every position of every worker, in order.
+    paths: Vec<Vec<Position>>,
+    /// The first and last visit of every house.
+    visits: HashMap<Position, (Visit, Visit)>,
+    /// The number of distinct houses visited after each step.
+    distinct: Vec<usize>,
+}
+
+impl History {
+    pub fn new(route: &Route, workers: usize) -> Self {
+        let origin = Position::default();
+        let mut paths = vec![vec![origin]; workers];
+        let mut visits = HashMap::new();
+        visits.insert(
+            origin,
+            (
+                Visit { step: 0, worker: 0 },
+                Visit {
+                    step: 0,
+                    worker: workers - 1,
+                },
+            ),
+        );
+        let mut distinct = Vec::with_capacity(route.steps() + 1);
+        distinct.push(1);
+
+        for (idx, step) in route.moves().enumerate() {
+            let worker = idx % workers;
+            let path = &mut paths[worker];
+            let position = path.last().unwrap().step(step);
+            path.push(position);
+
+            let visit = Visit {
+                step: idx + 1,
+                worker,
+            };
+            visits
+                .entry(position)
+                .and_modify(|(_, last)| *last = visit)
+                .or_insert((visit, visit));
+            distinct.push(visits.len());
+        }
+
+        History {
+            workers,
+            paths,
+            visits,
+            distinct,
+        }
+    }
+
+    /// The number of moves in the route.
+    pub fn steps(&self) -> usize {
+        self.distinct.len() - 1
+    }
+
+    /// Where `worker` was after `step` moves in total had been made.
+    pub fn position(&self, worker: usize, step: usize) -> Option<Position> {
+        if worker >= self.workers || step > self.steps() {
+            return None;
+        }
+
+        // Worker k makes moves k, k + n, k + 2n, ... (0-based)
+        let moves = if step > worker {
+            (step - worker - 1) / self.workers + 1
+        } else {
+            0
+        };
+        self.paths[worker].get(moves).copied()
+    }
+
+    pub fn first_visit(&self, house: Position) -> Option<Visit> {
+        self.visits.get(&house).map(|(first, _)| *first)
+    }
+
+    pub fn last_visit(&self, house: Position) -> Option<Visit> {
+        self.visits.get(&house).map(|(_, last)| *last)
+    }
+
+    /// The number of distinct houses that had been visited after `step` moves.
+    pub fn distinct_by(&self, step: usize) -> Option<usize> {
+        self.distinct.get(step).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Alphabet;
+
+    fn history(route: &str, workers: usize) -> History {
+        History::new(&Alphabet::default().parse(route).unwrap(), workers)
+    }
+
+    #[test]
+    fn positions() {
+        let history = history("^>v<", 2);
+
+        assert_eq!(history.steps(), 4);
+        assert_eq!(history.position(0, 0), Some(Position(0, 0)));
+        assert_eq!(history.position(0, 1), Some(Position(0, 1)));
+        assert_eq!(history.position(1, 1), Some(Position(0, 0)));
+        assert_eq!(history.position(1, 2), Some(Position(1, 0)));
+        assert_eq!(history.position(0, 3), Some(Position(0, 0)));
+        assert_eq!(history.position(1, 4), Some(Position(0, 0)));
+        assert_eq!(history.position(2, 0), None);
+        assert_eq!(history.position(0, 5), None);
+    }
+
+    #[test]
+    fn visits() {
+        let history = history("^>v<", 2);
+
+        assert_eq!(
+            history.first_visit(Position(0, 0)),
+            Some(Visit { step: 0, worker: 0 })
+        );
+        assert_eq!(
+            history.last_visit(Position(0, 0)),
+            Some(Visit { step: 4, worker: 1 })
+        );
+        assert_eq!(
+            history.first_visit(Position(1, 0)),
+            Some(Visit { step: 2, worker: 1 })
+        );
+        assert_eq!(history.first_visit(Position(5, 5)), None);
+    }
+
+    #[test]
+    fn distinct() {
+        let history = history("^>v<", 2);
+
+        assert_eq!(
+            (0..=4)
+                .map(|step| history.distinct_by(step))
+                .collect::<Vec<_>>(),
+            vec![Some(1), Some(2), Some(3), Some(3), Some(3)]
+        );
+        assert_eq!(history.distinct_by(5), None);
+    }
+}
@@ -0,0 +1,213 @@
+use aoc_2015::cli::Args;
+use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::Answer;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+mod heatmap;
+mod history;
+mod moves;
+
+use moves::{Alphabet, Move, Route};
+
+#[derive(Default, Debug, Hash, PartialEq, Eq, Copy, Clone)]
+struct Position(i32, i32);
+
+impl Position {
+    fn step(&self, Move(dx, dy): Move) -> Self {
+        Self(self.0 + dx, self.1 + dy)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Delivery {
+    /// How many presents every visited house received.
+    visits: HashMap<Position, u32>,
+    /// The number of distinct houses each worker delivered to.
+    per_worker: Vec<usize>,
+    /// The houses each worker delivered to, in order, starting at the origin.
+    /// Only recorded when asked for.
+    paths: Vec<Vec<Position>>,
+}
+
+/// Lets `workers` deliverers take turns following the moves in a single pass.
+/// Everybody starts by delivering to the house at the origin. The paths are
+/// only kept if `record_paths` is set.
+fn deliver(route: &Route, workers: usize, record_paths: bool) -> Delivery {
+    let origin = Position::default();
+    let mut positions = vec![origin; workers];
+    let mut paths = if record_paths {
+        vec![vec![origin]; workers]
+    } else {
+        Vec::new()
+    };
+    let mut per_worker = vec![1; workers];
+    let mut visits = HashMap::new();
+    visits.insert(origin, workers as u32);
+    // The houses a worker has been to, when there is more than one worker to
+    // tell apart.
+    let mut seen: HashSet<(usize, Position)> = HashSet::new();
+
+    for (idx, step) in route.moves().enumerate() {
+        let worker = idx % workers;
+        let position = positions[worker].step(step);
+        positions[worker] = position;
+
+        let count = visits.entry(position).or_insert(0);
+        *count += 1;
+        let first_visit = if workers == 1 {
+            *count == 1
+        } else {
+            position != origin && seen.insert((worker, position))
+        };
+        if first_visit {
+            per_worker[worker] += 1;
+        }
+        if let Some(path) = paths.get_mut(worker) {
+            path.push(position);
+        }
+    }
+
+    Delivery {
+        visits,
+        per_worker,
+        paths,
+    }
+}
+
+fn solve_a(route: &Route) -> Answer {
+    deliver(route, 1, false).visits.len().into()
+}
+
+fn solve_b(route: &Route) -> Answer {
+    deliver(route, 2, false).visits.len().into()
+}
+
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+
+    let alphabet = Alphabet {
+        strict: args.flag("strict"),
+        diagonal: args.flag("diagonal"),
+        run_length: args.flag("run-length"),
+    };
+
+    let lines = logging::read(read_input);
+    let line = lines.first().ok_or("Error reading input")?;
+    let moves = logging::parse(|| alphabet.parse(line)).map_err(|e| e.to_string())?;
+
+    let solution_a = logging::solve("a", || solve_a(&moves));
+    let solution_b = logging::solve("b", || solve_b(&moves));
+
+    println!("{}", solution_a);
+    println!("{}", solution_b);
+
+    let workers: usize = match args.value("workers").map(str::parse) {
+        None => 1,
+        Some(Ok(workers)) if workers > 0 => workers,
+        Some(_) => return Err("Invalid number of workers".to_string()),
+    };
+    // Following the whole route is only needed for the per-worker summary
+    // and the images, and indexing it only for the step and house queries.
+    if args.flag("workers") || args.value("heatmap").is_some() || args.value("svg").is_some() {
+        let delivery = deliver(&moves, workers, args.value("svg").is_some());
+
+        if args.flag("workers") {
+            println!(
+                "{} houses, per worker: {:?}",
+                delivery.visits.len(),
+                delivery.per_worker
+            );
+        }
+
+        if let Some(path) = args.value("heatmap") {
+            fs::write(path, heatmap::to_ppm(&delivery.visits)?).map_err(|e| e.to_string())?;
+        }
+
+        if let Some(path) = args.value("svg") {
+            fs::write(path, heatmap::to_svg(&delivery)).map_err(|e| e.to_string())?;
+        }
+    }
+
+    if args.value("at").is_some() || args.value("house").is_some() {
+        let history = history::History::new(&moves, workers);
+
+        if let Some(step) = args.value("at") {
+            let step: usize = step
+                .parse()
+                .map_err(|_| format!("Invalid step '{}'", step))?;
+            let distinct = history
+                .distinct_by(step)
+                .ok_or_else(|| format!("The route only has {} steps", history.steps()))?;
+            let positions: Vec<Position> = (0..workers)
+                .filter_map(|worker| history.position(worker, step))
+                .collect();
+
+            println!(
+                "step {}: {} houses, workers at {:?}",
+                step, distinct, positions
+            );
+        }
+
+        if let Some(house) = args.value("house") {
+            let house = match house.split_once(',').map(|(x, y)| (x.parse(), y.parse())) {
+                Some((Ok(x), Ok(y))) => Position(x, y),
+                _ => return Err(format!("Invalid house '{}', expected x,y", house)),
+            };
+
+            match (history.first_visit(house), history.last_visit(house)) {
+                (Some(first), Some(last)) => println!(
+                    "{:?}: first visited at step {} by worker {}, last at step {} by worker {}",
+                    house, first.step, first.worker, last.step, last.worker
+                ),
+                _ => println!("{:?}: never visited", house),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(route: &str) -> Route {
+        Alphabet::default().parse(route).unwrap()
+    }
+
+    #[test]
+    fn single_worker() {
+        assert_eq!(solve_a(&moves(">")), Answer::from(2));
+        assert_eq!(solve_a(&moves("^>v<")), Answer::from(4));
+        assert_eq!(solve_a(&moves("^v^v^v^v^v")), Answer::from(2));
+    }
+
+    #[test]
+    fn two_workers() {
+        assert_eq!(solve_b(&moves("^v")), Answer::from(3));
+        assert_eq!(solve_b(&moves("^>v<")), Answer::from(3));
+        assert_eq!(solve_b(&moves("^v^v^v^v^v")), Answer::from(11));
+    }
+
+    #[test]
+    fn per_worker() {
+        let delivery = deliver(&moves("^>v<^"), 3, true);
+
+        assert_eq!(delivery.visits.len(), 6);
+        assert_eq!(delivery.visits[&Position(0, 0)], 3);
+        assert_eq!(delivery.per_worker, vec![3, 3, 2]);
+        assert_eq!(
+            delivery.paths[1],
+            vec![Position(0, 0), Position(1, 0), Position(1, 1)]
+        );
+
+        // Coming back to a house, or to the origin, doesn't count again.
+        let delivery = deliver(&moves("^^^^vvvv"), 2, false);
+        assert_eq!(delivery.per_worker, vec![3, 3]);
+        assert!(delivery.paths.is_empty());
+        assert_eq!(deliver(&moves("^v^>"), 1, false).per_worker, vec![3]);
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+use std::iter::repeat_n;
+
+/// A single step of a worker as `(dx, dy)`, with north being positive `y`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Move(pub i32, pub i32);
+
+/// The longest route, in moves. No position on a route that fits can
+/// overflow an `i32`.
+const MAX_MOVES: usize = i32::MAX as usize;
+
+/// A parsed route. Runs of the same move are kept as a move and a count, so
+/// a long run takes no more memory than a single move.
+#[derive(Debug, PartialEq, Default)]
+pub struct Route {
+    runs: Vec<(Move, usize)>,
+    len: usize,
+}
+
+impl Route {
+    /// The number of moves.
+    pub fn steps(&self) -> usize {
+        self.len
+    }
+
+    /// Every move of the route, with the runs expanded as they are walked.
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.runs
+            .iter()
+            .flat_map(|(step, count)| repeat_n(*step, *count))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseMoveError {
+    /// A character outside the alphabet, at a 1-based position.
+    UnknownMove { position: usize, character: char },
+    /// A run length that doesn't fit in a `usize`, at a 1-based position.
+    InvalidCount { position: usize },
+    /// The route gets longer than `MAX_MOVES` at a 1-based position.
+    TooLong { position: usize },
+}
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMoveError::UnknownMove {
+                position,
+                character,
+            } => write!(f, "Unknown move '{}' at position {}", character, position),
+            ParseMoveError::InvalidCount { position } => {
+                write!(f, "Invalid run length at position {}", position)
+            }
+            ParseMoveError::TooLong { position } => write!(
+                f,
+                "The route is longer than {} moves at position {}",
+                MAX_MOVES, position
+            ),
+        }
+    }
+}
+
+/// Describes which characters make up a route.
+///
+/// The default is the puzzle's `^>v<` where anything else is a turn spent
+/// standing still.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Alphabet {
+    /// Reject unknown characters instead of treating them as standing still.
+    pub strict: bool,
+    /// Also accept `q`, `e`, `z` and `c` for the diagonals, laid out around
+    /// `s` like on a keyboard.
+    pub diagonal: bool,
+    /// Allow a move to be followed by a repeat count, so `^5>3` is
+    /// `^^^^^>>>`.
+    pub run_length: bool,
+}
+
+impl Alphabet {
+    fn direction(&self, c: char) -> Option<Move> {
+        match c {
+            '^' => Some(Move(0, 1)),
+            '>' => Some(Move(1, 0)),
+            'v' => Some(Move(0, -1)),
+            '<' => Some(Move(-1, 0)),
+            'q' if self.diagonal => Some(Move(-1, 1)),
+            'e' if self.diagonal => Some(Move(1, 1)),
+            'z' if self.diagonal => Some(Move(-1, -1)),
+            'c' if self.diagonal => Some(Move(1, -1)),
+            _ => None,
+        }
+    }
+
+    pub fn parse(&self, route: &str) -> Result<Route, ParseMoveError> {
+        let mut moves = Route::default();
+        let mut chars = route.chars().enumerate().peekable();
+
+        while let Some((idx, c)) = chars.next() {
+            let step = match self.direction(c) {
+                Some(step) => step,
+                None if self.strict => {
+                    return Err(ParseMoveError::UnknownMove {
+                        position: idx + 1,
+                        character: c,
+                    })
+                }
+                None => Move(0, 0),
+            };
+
+            let mut count: Option<usize> = None;
+            while let Some(digit) = chars
+                .peek()
+                .filter(|_| self.run_length)
+                .and_then(|(_, c)| c.to_digit(10))
+            {
+                count = count
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|count| count.checked_add(digit as usize))
+                    .map(Some)
+                    .ok_or(ParseMoveError::InvalidCount { position: idx + 2 })?;
+                chars.next();
+            }
+
+            let count = count.unwrap_or(1);
+            moves.len = moves
+                .len
+                .checked_add(count)
+                .filter(|len| *len <= MAX_MOVES)
+                .ok_or(ParseMoveError::TooLong { position: idx + 1 })?;
+            match moves.runs.last_mut() {
+                Some((last, run)) if *last == step => *run += count,
+                _ if count > 0 => moves.runs.push((step, count)),
+                _ => {}
+            }
+        }
+
+        Ok(moves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(route: Result<Route, ParseMoveError>) -> Result<Vec<Move>, ParseMoveError> {
+        route.map(|route| route.moves().collect())
+    }
+
+    #[test]
+    fn default() {
+        assert_eq!(
+            moves(Alphabet::default().parse("^>x")),
+            Ok(vec![Move(0, 1), Move(1, 0), Move(0, 0)])
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let alphabet = Alphabet {
+            strict: true,
+            ..Alphabet::default()
+        };
+
+        assert_eq!(
+            moves(alphabet.parse("^>q")),
+            Err(ParseMoveError::UnknownMove {
+                position: 3,
+                character: 'q'
+            })
+        );
+        assert_eq!(
+            alphabet.parse("^>q").unwrap_err().to_string(),
+            "Unknown move 'q' at position 3"
+        );
+    }
+
+    #[test]
+    fn diagonal() {
+        let alphabet = Alphabet {
+            strict: true,
+            diagonal: true,
+            ..Alphabet::default()
+        };
+
+        assert_eq!(
+            moves(alphabet.parse("qezc")),
+            Ok(vec![Move(-1, 1), Move(1, 1), Move(-1, -1), Move(1, -1)])
+        );
+    }
+
+    #[test]
+    fn run_length() {
+        let alphabet = Alphabet {
+            strict: true,
+            run_length: true,
+            ..Alphabet::default()
+        };
+
+        assert_eq!(
+            moves(alphabet.parse("^3>v12<0")),
+            Ok([vec![Move(0, 1); 3], vec![Move(1, 0)], vec![Move(0, -1); 12]].concat())
+        );
+        assert_eq!(
+            moves(alphabet.parse("^99999999999999999999999")),
+            Err(ParseMoveError::InvalidCount { position: 2 })
+        );
+        assert!(alphabet.parse("5^").is_err());
+
+        let route = alphabet.parse("^4000000>^2").unwrap();
+        assert_eq!(route.steps(), 4_000_003);
+        assert_eq!(
+            route.runs,
+            vec![(Move(0, 1), 4_000_000), (Move(1, 0), 1), (Move(0, 1), 2)]
+        );
+        assert_eq!(
+            alphabet.parse("^2147483647>"),
+            Err(ParseMoveError::TooLong { position: 12 })
+        );
+        assert_eq!(
+            moves(alphabet.parse("^4000000000")),
+            Err(ParseMoveError::TooLong { position: 1 })
+        );
+    }
+}
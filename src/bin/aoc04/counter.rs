@@ -0,0 +1,103 @@
+/// The number of decimal digits in `u64::MAX`.
+const MAX_DIGITS: usize = 20;
+
+/// A decimal number kept as ASCII digits, so it can be appended to a hash
+/// input without formatting or allocating on every increment.
+#[derive(Debug, Clone)]
+pub struct DecimalCounter {
+    /// The digits are right-aligned in the buffer, starting at `start`.
+    digits: [u8; MAX_DIGITS],
+    start: usize,
+    value: u64,
+}
+
+impl DecimalCounter {
+    pub fn new(value: u64) -> Self {
+        let mut digits = [b'0'; MAX_DIGITS];
+        let mut start = MAX_DIGITS;
+        let mut rest = value;
+
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (rest % 10) as u8;
+            rest /= 10;
+            if rest == 0 {
+                break;
+            }
+        }
+
+        DecimalCounter {
+            digits,
+            start,
+            value,
+        }
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.digits[self.start..]
+    }
+
+    /// Adds one by carrying through the trailing nines, growing by a digit
+    /// when all of them were nines. Returns `false` and leaves the counter
+    /// alone at `u64::MAX`, which has no successor.
+    pub fn increment(&mut self) -> bool {
+        self.value = match self.value.checked_add(1) {
+            Some(value) => value,
+            None => return false,
+        };
+
+        for idx in (self.start..MAX_DIGITS).rev() {
+            if self.digits[idx] == b'9' {
+                self.digits[idx] = b'0';
+            } else {
+                self.digits[idx] += 1;
+                return true;
+            }
+        }
+
+        self.start -= 1;
+        self.digits[self.start] = b'1';
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        assert_eq!(DecimalCounter::new(0).as_bytes(), b"0");
+        assert_eq!(DecimalCounter::new(609043).as_bytes(), b"609043");
+        assert_eq!(
+            DecimalCounter::new(u64::MAX).as_bytes(),
+            u64::MAX.to_string().as_bytes()
+        );
+    }
+
+    #[test]
+    fn increments() {
+        let mut counter = DecimalCounter::new(0);
+
+        for n in 0..12_345u64 {
+            assert_eq!(counter.as_bytes(), n.to_string().as_bytes());
+            assert_eq!(counter.value(), n);
+            assert!(counter.increment());
+        }
+    }
+
+    #[test]
+    fn stops_at_max() {
+        let mut counter = DecimalCounter::new(u64::MAX - 1);
+
+        assert!(counter.increment());
+        assert_eq!(counter.value(), u64::MAX);
+        assert!(!counter.increment());
+        assert_eq!(counter.value(), u64::MAX);
+        assert_eq!(counter.as_bytes(), u64::MAX.to_string().as_bytes());
+    }
+}
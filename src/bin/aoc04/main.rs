@@ -0,0 +1,158 @@
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::thread;
+use std::time::Instant;
+
+use aoc_2015::cli::Args;
+use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::Answer;
+
+mod counter;
+mod md5_lanes;
+mod miner;
+mod progress;
+
+use miner::{HashFunction, Predicate, Scanner};
+use progress::{Checkpoint, Checkpoints, Reporter};
+
+/// The number of nonces every scanner hashes in `--bench`.
+const BENCH_NONCES: u64 = 2_000_000;
+
+/// How searches are run, taken from the command line.
+struct Options<'a> {
+    threads: usize,
+    /// `--checkpoint FILE` resumes searches from and saves them to `FILE`.
+    checkpoint: Option<&'a str>,
+    /// `--progress` prints the hash rate and an estimate to stderr.
+    progress: bool,
+}
+
+fn find_hash(
+    key: &str,
+    hash: HashFunction,
+    scanner: Scanner,
+    predicate: &Predicate,
+    options: &Options,
+) -> Result<u64, String> {
+    predicate.check(hash)?;
+    if options.checkpoint.is_none() && !options.progress {
+        return miner::mine(key, scanner, predicate, options.threads)
+            .ok_or_else(|| exhausted(0, predicate));
+    }
+
+    let checkpoints = match options.checkpoint {
+        Some(path) => Checkpoints::load(Path::new(path))?,
+        None => Checkpoints::default(),
+    };
+    let checkpoint = Checkpoint {
+        hash,
+        predicate: predicate.clone(),
+        scanned: checkpoints.resume(hash, predicate, key),
+        key: key.to_string(),
+    };
+    let reporter = Reporter::new(
+        checkpoint,
+        checkpoints,
+        options.checkpoint.map(Into::into),
+        options.progress,
+    );
+
+    let nonce = miner::search(
+        key,
+        scanner,
+        predicate,
+        options.threads,
+        reporter.start(),
+        |scanned| reporter.update(scanned),
+    );
+    reporter.finish(nonce.unwrap_or(u64::MAX))?;
+    nonce.ok_or_else(|| exhausted(reporter.start(), predicate))
+}
+
+fn exhausted(start: u64, predicate: &Predicate) -> String {
+    format!(
+        "No nonce from {} below {} satisfies {}",
+        start,
+        u64::MAX,
+        predicate
+    )
+}
+
+fn solve_a(key: &str, scanner: Scanner, options: &Options) -> Result<Answer, String> {
+    let predicate = Predicate::LeadingZeroNibbles(5);
+    find_hash(key, HashFunction::Md5, scanner, &predicate, options).map(Answer::from)
+}
+
+fn solve_b(key: &str, scanner: Scanner, options: &Options) -> Result<Answer, String> {
+    let predicate = Predicate::LeadingZeroNibbles(6);
+    find_hash(key, HashFunction::Md5, scanner, &predicate, options).map(Answer::from)
+}
+
+/// Lets every MD5 scanner hash the same nonces on a single thread, with a
+/// predicate that never matches, and reports how fast they are.
+fn bench(key: &str) {
+    let never = Predicate::LeadingZeroBits(129);
+    let rates = miner::scanners(HashFunction::Md5).run_all(|scanner| {
+        let start = Instant::now();
+        scanner(key, &never, 0..BENCH_NONCES, &AtomicU64::new(u64::MAX));
+        BENCH_NONCES as f64 / start.elapsed().as_secs_f64() / 1e6
+    });
+
+    for (name, rate) in rates {
+        println!("{}: {:.2} MH/s", name, rate);
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+    let threads = match args.value("threads").map(str::parse) {
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+        Some(Ok(threads)) if threads > 0 => threads,
+        Some(_) => return Err("Invalid number of threads".to_string()),
+    };
+
+    let options = Options {
+        threads,
+        checkpoint: args.value("checkpoint"),
+        progress: args.flag("progress"),
+    };
+
+    // `--predicate P` with `--hash H` is checked before the parts are solved.
+    let custom = match args.value("predicate") {
+        Some(predicate) => {
+            let hash: HashFunction = args.value("hash").unwrap_or("md5").parse()?;
+            let predicate: Predicate = predicate.parse()?;
+            predicate.check(hash)?;
+            Some((hash, predicate))
+        }
+        None => None,
+    };
+
+    let data = logging::read(read_input);
+    let key = data.first().ok_or("Error reading input")?;
+
+    if args.flag("bench") {
+        bench(key);
+        return Ok(());
+    }
+
+    let scanner = miner::scanners(HashFunction::Md5).select(args.value("impl"))?;
+    let solution_a = logging::solve("a", || solve_a(key, scanner, &options));
+    let solution_b = logging::solve("b", || solve_b(key, scanner, &options));
+
+    println!("{}", solution_a?);
+    println!("{}", solution_b?);
+
+    if let Some((hash, predicate)) = custom {
+        let scanner = miner::scanners(hash).select(args.value("impl"))?;
+        let nonce = logging::solve("custom", || {
+            find_hash(key, hash, scanner, &predicate, &options).map(Answer::from)
+        });
+
+        println!("{}", nonce?);
+    }
+
+    Ok(())
+}
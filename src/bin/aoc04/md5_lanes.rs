@@ -0,0 +1,175 @@
+//! MD5 for messages that fit in a single block, computed for several
+//! messages at once. Every step works on an array with one word per lane,
+//! which leaves the compiler free to vectorize it.
+
+/// The longest message that still fits in one padded block.
+pub const MAX_MESSAGE_LEN: usize = 55;
+
+const INIT: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+
+const SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const CONSTANTS: [u32; 64] = [
+    0xd76a_a478,
+    0xe8c7_b756,
+    0x2420_70db,
+    0xc1bd_ceee,
+    0xf57c_0faf,
+    0x4787_c62a,
+    0xa830_4613,
+    0xfd46_9501,
+    0x6980_98d8,
+    0x8b44_f7af,
+    0xffff_5bb1,
+    0x895c_d7be,
+    0x6b90_1122,
+    0xfd98_7193,
+    0xa679_438e,
+    0x49b4_0821,
+    0xf61e_2562,
+    0xc040_b340,
+    0x265e_5a51,
+    0xe9b6_c7aa,
+    0xd62f_105d,
+    0x0244_1453,
+    0xd8a1_e681,
+    0xe7d3_fbc8,
+    0x21e1_cde6,
+    0xc337_07d6,
+    0xf4d5_0d87,
+    0x455a_14ed,
+    0xa9e3_e905,
+    0xfcef_a3f8,
+    0x676f_02d9,
+    0x8d2a_4c8a,
+    0xfffa_3942,
+    0x8771_f681,
+    0x6d9d_6122,
+    0xfde5_380c,
+    0xa4be_ea44,
+    0x4bde_cfa9,
+    0xf6bb_4b60,
+    0xbebf_bc70,
+    0x289b_7ec6,
+    0xeaa1_27fa,
+    0xd4ef_3085,
+    0x0488_1d05,
+    0xd9d4_d039,
+    0xe6db_99e5,
+    0x1fa2_7cf8,
+    0xc4ac_5665,
+    0xf429_2244,
+    0x432a_ff97,
+    0xab94_23a7,
+    0xfc93_a039,
+    0x655b_59c3,
+    0x8f0c_cc92,
+    0xffef_f47d,
+    0x8584_5dd1,
+    0x6fa8_7e4f,
+    0xfe2c_e6e0,
+    0xa301_4314,
+    0x4e08_11a1,
+    0xf753_7e82,
+    0xbd3a_f235,
+    0x2ad7_d2bb,
+    0xeb86_d391,
+];
+
+/// Pads a message of at most `MAX_MESSAGE_LEN` bytes into a single block of
+/// little endian words.
+pub fn pad(message: &[u8]) -> [u32; 16] {
+    let mut block = [0u8; 64];
+    block[..message.len()].copy_from_slice(message);
+    block[message.len()] = 0x80;
+    block[56..].copy_from_slice(&(message.len() as u64 * 8).to_le_bytes());
+
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    words
+}
+
+/// Runs the MD5 compression function on `N` padded blocks, stored word by
+/// word with one lane per message, and returns the digests.
+pub fn digest<const N: usize>(blocks: &[[u32; N]; 16]) -> [[u8; 16]; N] {
+    let mut a = [INIT[0]; N];
+    let mut b = [INIT[1]; N];
+    let mut c = [INIT[2]; N];
+    let mut d = [INIT[3]; N];
+
+    for i in 0..64 {
+        let (mixed, g): ([u32; N], usize) = match i / 16 {
+            0 => (lanes(|l| (b[l] & c[l]) | (!b[l] & d[l])), i),
+            1 => (lanes(|l| (d[l] & b[l]) | (!d[l] & c[l])), (5 * i + 1) % 16),
+            2 => (lanes(|l| b[l] ^ c[l] ^ d[l]), (3 * i + 5) % 16),
+            _ => (lanes(|l| c[l] ^ (b[l] | !d[l])), (7 * i) % 16),
+        };
+
+        let rotated: [u32; N] = lanes(|l| {
+            a[l].wrapping_add(mixed[l])
+                .wrapping_add(CONSTANTS[i])
+                .wrapping_add(blocks[g][l])
+                .rotate_left(SHIFTS[i])
+        });
+
+        a = d;
+        d = c;
+        c = b;
+        b = lanes(|l| b[l].wrapping_add(rotated[l]));
+    }
+
+    let mut digests = [[0u8; 16]; N];
+    for (lane, digest) in digests.iter_mut().enumerate() {
+        let words = [
+            a[lane].wrapping_add(INIT[0]),
+            b[lane].wrapping_add(INIT[1]),
+            c[lane].wrapping_add(INIT[2]),
+            d[lane].wrapping_add(INIT[3]),
+        ];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(words.iter()) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+    digests
+}
+
+fn lanes<const N: usize>(f: impl Fn(usize) -> u32) -> [u32; N] {
+    let mut result = [0; N];
+    for (lane, value) in result.iter_mut().enumerate() {
+        *value = f(lane);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use md5::{Digest, Md5};
+
+    #[test]
+    fn matches_md5() {
+        let messages: Vec<Vec<u8>> = (0..=MAX_MESSAGE_LEN)
+            .map(|len| (0..len).map(|n| (n * 7 + len) as u8).collect())
+            .collect();
+
+        for chunk in messages.chunks(4) {
+            let mut blocks = [[0u32; 4]; 16];
+            for (lane, message) in chunk.iter().enumerate() {
+                for (word, value) in pad(message).iter().enumerate() {
+                    blocks[word][lane] = *value;
+                }
+            }
+
+            let digests = digest(&blocks);
+            for (lane, message) in chunk.iter().enumerate() {
+                assert_eq!(digests[lane][..], Md5::digest(message)[..]);
+            }
+        }
+    }
+}
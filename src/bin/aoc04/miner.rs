@@ -0,0 +1,547 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use md5::{Digest, Md5};
+use sha1::Sha1;
+use sha2::Sha256;
+use tracing::trace;
+
+use aoc_2015::variants::Implementations;
+
+use crate::counter::DecimalCounter;
+use crate::md5_lanes;
+
+/// The number of nonces a thread claims at a time.
+pub const CHUNK_SIZE: u64 = 1 << 14;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HashFunction {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl FromStr for HashFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "md5" => Ok(HashFunction::Md5),
+            "sha1" => Ok(HashFunction::Sha1),
+            "sha256" => Ok(HashFunction::Sha256),
+            _ => Err(format!(
+                "Unknown hash function '{}', expected md5, sha1 or sha256",
+                s
+            )),
+        }
+    }
+}
+
+impl HashFunction {
+    /// The length of a hash in bytes.
+    pub fn output_len(self) -> usize {
+        match self {
+            HashFunction::Md5 => 16,
+            HashFunction::Sha1 => 20,
+            HashFunction::Sha256 => 32,
+        }
+    }
+}
+
+impl fmt::Display for HashFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashFunction::Md5 => write!(f, "md5"),
+            HashFunction::Sha1 => write!(f, "sha1"),
+            HashFunction::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+/// The condition a hash has to meet.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Predicate {
+    /// Starts with this many zero hex digits.
+    LeadingZeroNibbles(usize),
+    /// Starts with this many zero bits.
+    LeadingZeroBits(usize),
+    /// Its hex representation starts with these (lowercase) digits.
+    HexPrefix(String),
+    /// Read as a fraction, it is below the target. A target shorter than
+    /// the hash is padded with zeroes on the right.
+    Below(Vec<u8>),
+}
+
+fn check_hash(hash: &[u8], leading_zeroes: usize) -> bool {
+    hash.iter()
+        .flat_map(|byte| vec![byte >> 4, byte & 0xf])
+        .take(leading_zeroes)
+        .all(|n| n == 0)
+}
+
+fn nibble(hash: &[u8], idx: usize) -> Option<u8> {
+    hash.get(idx / 2).map(|byte| {
+        if idx.is_multiple_of(2) {
+            byte >> 4
+        } else {
+            byte & 0xf
+        }
+    })
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(s.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
+
+impl Predicate {
+    pub fn matches(&self, hash: &[u8]) -> bool {
+        match self {
+            Predicate::LeadingZeroNibbles(n) => check_hash(hash, *n),
+            Predicate::LeadingZeroBits(n) => {
+                let (bytes, bits) = (n / 8, n % 8);
+
+                hash.len() * 8 >= *n
+                    && hash[..bytes].iter().all(|byte| *byte == 0)
+                    && (bits == 0 || hash[bytes] >> (8 - bits) == 0)
+            }
+            Predicate::HexPrefix(prefix) => prefix
+                .chars()
+                .enumerate()
+                .all(|(idx, c)| nibble(hash, idx).map(u32::from) == c.to_digit(16)),
+            Predicate::Below(target) => {
+                let len = target.len().min(hash.len());
+                hash[..len] < target[..len]
+            }
+        }
+    }
+
+    /// Rejects a predicate that no hash from `hash` can satisfy, since
+    /// searching for one would never end.
+    pub fn check(&self, hash: HashFunction) -> Result<(), String> {
+        let bytes = hash.output_len();
+        let satisfiable = match self {
+            Predicate::LeadingZeroNibbles(n) => *n <= 2 * bytes,
+            Predicate::LeadingZeroBits(n) => *n <= 8 * bytes,
+            Predicate::HexPrefix(prefix) => prefix.len() <= 2 * bytes,
+            Predicate::Below(target) => target.iter().take(bytes).any(|byte| *byte != 0),
+        };
+
+        if satisfiable {
+            Ok(())
+        } else {
+            Err(format!("No {} hash satisfies {}", hash, self))
+        }
+    }
+
+    /// The chance that a random hash matches.
+    pub fn probability(&self) -> f64 {
+        match self {
+            Predicate::LeadingZeroNibbles(n) => 16f64.powi(-(*n as i32)),
+            Predicate::LeadingZeroBits(n) => 2f64.powi(-(*n as i32)),
+            Predicate::HexPrefix(prefix) => 16f64.powi(-(prefix.len() as i32)),
+            Predicate::Below(target) => target
+                .iter()
+                .rev()
+                .fold(0.0, |fraction, byte| (fraction + *byte as f64) / 256.0),
+        }
+    }
+}
+
+/// Parses `zeroes:N`, `bits:N`, `prefix:HEX` or `below:HEX`.
+impl FromStr for Predicate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid predicate '{}'", s);
+        let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+
+        match kind {
+            "zeroes" => value
+                .parse()
+                .map(Predicate::LeadingZeroNibbles)
+                .map_err(|_| invalid()),
+            "bits" => value
+                .parse()
+                .map(Predicate::LeadingZeroBits)
+                .map_err(|_| invalid()),
+            "prefix" if value.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Ok(Predicate::HexPrefix(value.to_ascii_lowercase()))
+            }
+            "below" => parse_hex(value).map(Predicate::Below).ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::LeadingZeroNibbles(n) => write!(f, "zeroes:{}", n),
+            Predicate::LeadingZeroBits(n) => write!(f, "bits:{}", n),
+            Predicate::HexPrefix(prefix) => write!(f, "prefix:{}", prefix),
+            Predicate::Below(target) => {
+                write!(f, "below:")?;
+                target.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+        }
+    }
+}
+
+/// Scans a range of nonces for the first one whose hash satisfies the
+/// predicate, giving up once another thread has found a smaller nonce.
+pub type Scanner = fn(&str, &Predicate, Range<u64>, &AtomicU64) -> Option<u64>;
+
+/// Hashes one nonce at a time with a `Digest` implementation, starting from
+/// a hasher that has already seen the key.
+fn scan<D: Digest + Clone>(
+    key: &str,
+    predicate: &Predicate,
+    range: Range<u64>,
+    best: &AtomicU64,
+) -> Option<u64> {
+    let mut prefix = D::new();
+    prefix.update(key);
+    let mut counter = DecimalCounter::new(range.start);
+
+    while counter.value() < range.end {
+        if counter.value() >= best.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let mut hasher = prefix.clone();
+        hasher.update(counter.as_bytes());
+        if predicate.matches(&hasher.finalize()) {
+            return Some(counter.value());
+        }
+        if !counter.increment() {
+            break;
+        }
+    }
+
+    None
+}
+
+/// The number of nonces hashed at once by `scan_md5_lanes`.
+const LANES: usize = 8;
+
+/// Hashes `LANES` nonces at once, falling back to `scan` when the key is too
+/// long for the key and nonce to fit in a single MD5 block.
+fn scan_md5_lanes(
+    key: &str,
+    predicate: &Predicate,
+    range: Range<u64>,
+    best: &AtomicU64,
+) -> Option<u64> {
+    if key.len() + 20 > md5_lanes::MAX_MESSAGE_LEN {
+        return scan::<Md5>(key, predicate, range, best);
+    }
+
+    let key = key.as_bytes();
+    let mut message = [0u8; md5_lanes::MAX_MESSAGE_LEN];
+    message[..key.len()].copy_from_slice(key);
+    let mut counter = DecimalCounter::new(range.start);
+    let mut start = range.start;
+
+    while start < range.end {
+        if start >= best.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let mut count = (range.end - start).min(LANES as u64) as usize;
+        let mut padded = [[0u32; 16]; LANES];
+        for (lane, block) in padded.iter_mut().enumerate().take(count) {
+            let digits = counter.as_bytes();
+            let len = key.len() + digits.len();
+            message[key.len()..len].copy_from_slice(digits);
+            *block = md5_lanes::pad(&message[..len]);
+            if !counter.increment() {
+                count = lane + 1;
+                break;
+            }
+        }
+
+        let mut blocks = [[0u32; LANES]; 16];
+        for (word, lanes) in blocks.iter_mut().enumerate() {
+            for (lane, block) in lanes.iter_mut().zip(padded.iter()) {
+                *lane = block[word];
+            }
+        }
+
+        let digests = md5_lanes::digest(&blocks);
+        if let Some(lane) = digests[..count]
+            .iter()
+            .position(|digest| predicate.matches(digest))
+        {
+            return Some(start + lane as u64);
+        }
+        start += count as u64;
+    }
+
+    None
+}
+
+/// The available scanners for a hash function, the default first. The
+/// lanes kernel is kept as an alternative; see `--bench` for how it
+/// compares on a given machine.
+pub fn scanners(hash: HashFunction) -> Implementations<Scanner> {
+    match hash {
+        HashFunction::Md5 => Implementations::new("md5-crate", scan::<Md5> as Scanner)
+            .register("lanes", scan_md5_lanes),
+        HashFunction::Sha1 => Implementations::new("sha1-crate", scan::<Sha1> as Scanner),
+        HashFunction::Sha256 => Implementations::new("sha2-crate", scan::<Sha256> as Scanner),
+    }
+}
+
+/// Finds the smallest nonce for which the hash of `key` followed by the
+/// nonce in decimal satisfies `predicate`, if there is one below `u64::MAX`.
+pub fn mine(key: &str, scanner: Scanner, predicate: &Predicate, threads: usize) -> Option<u64> {
+    search(key, scanner, predicate, threads, 0, |_| {})
+}
+
+/// The chunks that have been scanned completely, used to track up to which
+/// nonce the search is done.
+struct Frontier {
+    /// Every chunk below this one has been scanned.
+    next: u64,
+    /// Finished chunks above `next`.
+    finished: BTreeSet<u64>,
+}
+
+/// Finds the smallest nonce from `start` onwards, like `mine`, and calls
+/// `progress` with a new bound whenever every nonce below it has been
+/// scanned. The bound never passes the nonce that is eventually returned, so
+/// it is safe to resume from. Nonces are searched up to, but not including,
+/// `u64::MAX`, which stands for not having found one yet.
+///
+/// The nonces are searched in chunks that idle threads claim from a shared
+/// counter. Chunks are claimed in increasing order, so once a hit is found
+/// every lower chunk is already being scanned and only chunks above it can be
+/// skipped. That way the smallest qualifying nonce is returned no matter how
+/// many threads take part.
+pub fn search(
+    key: &str,
+    scanner: Scanner,
+    predicate: &Predicate,
+    threads: usize,
+    start: u64,
+    progress: impl Fn(u64) + Sync,
+) -> Option<u64> {
+    let next_chunk = AtomicU64::new(0);
+    let best = AtomicU64::new(u64::MAX);
+    let frontier = Mutex::new(Frontier {
+        next: 0,
+        finished: BTreeSet::new(),
+    });
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let chunk = next_chunk.fetch_add(1, Ordering::Relaxed);
+                let from = match chunk_start(start, chunk) {
+                    Some(from) if from < best.load(Ordering::Relaxed) => from,
+                    _ => break,
+                };
+                let to = from.saturating_add(CHUNK_SIZE);
+
+                trace!(start = from, "searching chunk");
+                if let Some(n) = scanner(key, predicate, from..to, &best) {
+                    best.fetch_min(n, Ordering::Relaxed);
+                }
+
+                // A scan only stops early when a smaller nonce has been found,
+                // and `best` never grows, so this chunk was scanned completely.
+                if best.load(Ordering::Relaxed) >= to {
+                    let mut guard = frontier.lock().unwrap();
+                    let frontier = &mut *guard;
+                    frontier.finished.insert(chunk);
+
+                    let previous = frontier.next;
+                    while frontier.finished.remove(&frontier.next) {
+                        frontier.next += 1;
+                    }
+                    if frontier.next > previous {
+                        progress(chunk_start(start, frontier.next).unwrap_or(u64::MAX));
+                    }
+                }
+            });
+        }
+    });
+
+    Some(best.into_inner()).filter(|best| *best < u64::MAX)
+}
+
+/// The first nonce of the `chunk`th chunk from `start`, unless it is past
+/// `u64::MAX`.
+fn chunk_start(start: u64, chunk: u64) -> Option<u64> {
+    chunk
+        .checked_mul(CHUNK_SIZE)
+        .and_then(|offset| start.checked_add(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_checks() {
+        assert_eq!(check_hash(&vec![0, 0, 0, 1], 6), true);
+        assert_eq!(check_hash(&vec![0, 0, 0x05, 1], 6), false);
+        assert_eq!(check_hash(&vec![0, 0, 0x05, 1], 5), true);
+    }
+
+    #[test]
+    fn predicates() {
+        let hash = [0x00, 0x1f, 0xab];
+
+        assert!(Predicate::LeadingZeroNibbles(2).matches(&hash));
+        assert!(!Predicate::LeadingZeroNibbles(3).matches(&hash));
+        assert!(Predicate::LeadingZeroBits(11).matches(&hash));
+        assert!(!Predicate::LeadingZeroBits(12).matches(&hash));
+        assert!(!Predicate::LeadingZeroBits(32).matches(&hash));
+        assert!(Predicate::HexPrefix("001fa".to_string()).matches(&hash));
+        assert!(!Predicate::HexPrefix("001fb".to_string()).matches(&hash));
+        assert!(Predicate::Below(vec![0x00, 0x20]).matches(&hash));
+        assert!(!Predicate::Below(vec![0x00, 0x1f]).matches(&hash));
+    }
+
+    #[test]
+    fn probabilities() {
+        assert_eq!(Predicate::LeadingZeroNibbles(2).probability(), 1.0 / 256.0);
+        assert_eq!(Predicate::LeadingZeroBits(3).probability(), 0.125);
+        assert_eq!(
+            Predicate::HexPrefix("c0".to_string()).probability(),
+            1.0 / 256.0
+        );
+        assert_eq!(Predicate::Below(vec![0x80]).probability(), 0.5);
+        assert_eq!(
+            Predicate::Below(vec![0x00, 0x40]).probability(),
+            1.0 / 1024.0
+        );
+    }
+
+    #[test]
+    fn parse_predicates() {
+        for s in &["zeroes:5", "bits:20", "prefix:c0ffee", "below:00000fff"] {
+            assert_eq!(s.parse::<Predicate>().unwrap().to_string(), *s);
+        }
+        assert_eq!(
+            "prefix:C0FFEE".parse(),
+            Ok(Predicate::HexPrefix("c0ffee".to_string()))
+        );
+        assert!("prefix:xyz".parse::<Predicate>().is_err());
+        assert!("below:abc".parse::<Predicate>().is_err());
+        assert!("zeroes".parse::<Predicate>().is_err());
+    }
+
+    #[test]
+    fn unsatisfiable_predicates() {
+        let check = |s: &str, hash| s.parse::<Predicate>().unwrap().check(hash);
+
+        assert_eq!(check("bits:128", HashFunction::Md5), Ok(()));
+        assert_eq!(
+            check("bits:129", HashFunction::Md5),
+            Err("No md5 hash satisfies bits:129".to_string())
+        );
+        assert_eq!(check("bits:129", HashFunction::Sha1), Ok(()));
+        assert!(check("zeroes:33", HashFunction::Md5).is_err());
+        assert!(check("prefix:00000000000000000000000000000000", HashFunction::Md5).is_ok());
+        assert!(check(
+            "prefix:000000000000000000000000000000000",
+            HashFunction::Md5
+        )
+        .is_err());
+        assert!(check("below:", HashFunction::Sha256).is_err());
+        assert!(check("below:0000", HashFunction::Sha256).is_err());
+        assert!(check("below:0001", HashFunction::Sha256).is_ok());
+    }
+
+    #[test]
+    fn smallest_nonce() {
+        let expected = (0..)
+            .find(|n| check_hash(&Md5::digest(format!("abc{}", n).as_bytes()), 4))
+            .unwrap();
+
+        // The hit lies a few chunks in, so several threads are racing for it.
+        assert!(expected > 2 * CHUNK_SIZE);
+        let predicate = Predicate::LeadingZeroNibbles(4);
+        scanners(HashFunction::Md5).assert_agree(|scanner| {
+            assert_eq!(mine("abc", scanner, &predicate, 8), Some(expected));
+            mine("abc", scanner, &predicate, 1)
+        });
+    }
+
+    #[test]
+    fn resumes() {
+        let predicate = Predicate::LeadingZeroNibbles(4);
+        let scanner = scanners(HashFunction::Md5).select(None).unwrap();
+        let expected = mine("abc", scanner, &predicate, 1).unwrap();
+        let bounds = Mutex::new(Vec::new());
+
+        let nonce = search("abc", scanner, &predicate, 4, 1234, |scanned| {
+            bounds.lock().unwrap().push(scanned)
+        });
+        assert_eq!(nonce, Some(expected));
+
+        let bounds = bounds.into_inner().unwrap();
+        assert!(!bounds.is_empty());
+        assert!(bounds.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(bounds.iter().all(|bound| *bound <= expected));
+        assert_eq!(
+            search("abc", scanner, &predicate, 2, expected, |_| {}),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn exhausted() {
+        // Four zero bytes are unlikely in the last few nonces, and none of
+        // them is past `u64::MAX`.
+        let predicate = Predicate::LeadingZeroBits(32);
+        let start = u64::MAX - 3 * CHUNK_SIZE / 2;
+
+        let results = scanners(HashFunction::Md5).run_all(|scanner| {
+            (
+                search("abc", scanner, &predicate, 4, start, |bound| {
+                    assert!(bound >= start)
+                }),
+                search("abc", scanner, &predicate, 1, u64::MAX, |_| {}),
+            )
+        });
+        for (name, result) in results {
+            assert_eq!(result, (None, None), "{}", name);
+        }
+    }
+
+    #[test]
+    fn other_hashes() {
+        let predicate = Predicate::LeadingZeroBits(8);
+
+        let scanner = scanners(HashFunction::Sha256).select(None).unwrap();
+        let nonce = mine("abc", scanner, &predicate, 2).unwrap();
+        assert_eq!(Sha256::digest(format!("abc{}", nonce).as_bytes())[0], 0);
+
+        let scanner = scanners(HashFunction::Sha1).select(None).unwrap();
+        let nonce = mine("abc", scanner, &predicate, 2).unwrap();
+        assert_eq!(Sha1::digest(format!("abc{}", nonce).as_bytes())[0], 0);
+    }
+
+    #[test]
+    fn long_keys() {
+        let key = "a key that is too long to fit in a single block with a nonce";
+        let predicate = Predicate::LeadingZeroNibbles(2);
+
+        scanners(HashFunction::Md5).assert_agree(|scanner| mine(key, scanner, &predicate, 2));
+    }
+}
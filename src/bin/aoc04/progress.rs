@@ -0,0 +1,270 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::miner::{HashFunction, Predicate};
+
+/// How often progress is reported and checkpoints are written.
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How far a search has come: every nonce below `scanned` has been hashed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Checkpoint {
+    pub hash: HashFunction,
+    pub predicate: Predicate,
+    pub scanned: u64,
+    pub key: String,
+}
+
+/// The checkpoints of every search that used the same file, one per line as
+/// `HASH PREDICATE SCANNED KEY`. The key comes last because it may contain
+/// spaces.
+#[derive(Debug, PartialEq, Default)]
+pub struct Checkpoints {
+    entries: Vec<Checkpoint>,
+}
+
+impl Checkpoints {
+    /// Reads the checkpoints in `path`, which may not exist yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Error reading {}: {}", path.display(), e)),
+        }
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let entries = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(idx, line)| {
+                let invalid = || format!("Invalid checkpoint on line {}", idx + 1);
+                let mut fields = line.splitn(4, ' ');
+                let mut field = || fields.next().ok_or_else(invalid);
+
+                Ok(Checkpoint {
+                    hash: field()?.parse()?,
+                    predicate: field()?.parse()?,
+                    scanned: field()?.parse().map_err(|_| invalid())?,
+                    key: field()?.to_string(),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Checkpoints { entries })
+    }
+
+    /// Writes to a temporary file first, so an interrupted write never
+    /// destroys the previous checkpoints.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        fs::write(&temporary, self.to_string())?;
+        fs::rename(&temporary, path)
+    }
+
+    /// The nonce the search for `key` should resume from.
+    pub fn resume(&self, hash: HashFunction, predicate: &Predicate, key: &str) -> u64 {
+        self.entries
+            .iter()
+            .find(|c| c.hash == hash && c.predicate == *predicate && c.key == key)
+            .map_or(0, |c| c.scanned)
+    }
+
+    pub fn update(&mut self, checkpoint: Checkpoint) {
+        let existing = self.entries.iter_mut().find(|c| {
+            c.hash == checkpoint.hash
+                && c.predicate == checkpoint.predicate
+                && c.key == checkpoint.key
+        });
+
+        match existing {
+            Some(existing) => existing.scanned = existing.scanned.max(checkpoint.scanned),
+            None => self.entries.push(checkpoint),
+        }
+    }
+}
+
+impl fmt::Display for Checkpoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in &self.entries {
+            writeln!(f, "{} {} {} {}", c.hash, c.predicate, c.scanned, c.key)?;
+        }
+        Ok(())
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+struct State {
+    checkpoints: Checkpoints,
+    last_report: Instant,
+}
+
+/// Receives the progress of one search, printing the hash rate and an
+/// estimate of the remaining time to stderr and saving checkpoints, at most
+/// once every `REPORT_INTERVAL`.
+pub struct Reporter {
+    template: Checkpoint,
+    path: Option<PathBuf>,
+    verbose: bool,
+    started: Instant,
+    state: Mutex<State>,
+}
+
+impl Reporter {
+    pub fn new(
+        template: Checkpoint,
+        checkpoints: Checkpoints,
+        path: Option<PathBuf>,
+        verbose: bool,
+    ) -> Self {
+        let now = Instant::now();
+
+        Reporter {
+            template,
+            path,
+            verbose,
+            started: now,
+            state: Mutex::new(State {
+                checkpoints,
+                last_report: now,
+            }),
+        }
+    }
+
+    /// Where the search starts, which is past every nonce a previous run
+    /// already scanned.
+    pub fn start(&self) -> u64 {
+        self.template.scanned
+    }
+
+    pub fn update(&self, scanned: u64) {
+        let mut state = self.state.lock().unwrap();
+        if state.last_report.elapsed() < REPORT_INTERVAL {
+            return;
+        }
+        state.last_report = Instant::now();
+
+        if self.verbose {
+            eprintln!("{}", self.status(scanned));
+        }
+        if let Err(e) = self.save(&mut state.checkpoints, scanned) {
+            eprintln!("Error writing checkpoint: {}", e);
+        }
+    }
+
+    /// Saves the final checkpoint, which resumes right at the answer.
+    pub fn finish(&self, nonce: u64) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        self.save(&mut state.checkpoints, nonce)
+            .map_err(|e| format!("Error writing checkpoint: {}", e))
+    }
+
+    /// Since every nonce is an independent try, the expected number of
+    /// nonces still to go doesn't shrink while searching, so the estimate is
+    /// the same number of tries at the current rate.
+    fn status(&self, scanned: u64) -> String {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = (scanned - self.start()) as f64 / elapsed;
+        let remaining = 1.0 / self.template.predicate.probability() / rate;
+
+        format!(
+            "scanned {} nonces, {:.2} MH/s, expected to finish in {}",
+            scanned,
+            rate / 1e6,
+            if remaining.is_finite() && remaining < 1e9 {
+                format_duration(Duration::from_secs_f64(remaining))
+            } else {
+                "a very long time".to_string()
+            }
+        )
+    }
+
+    fn save(&self, checkpoints: &mut Checkpoints, scanned: u64) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        checkpoints.update(Checkpoint {
+            scanned,
+            ..self.template.clone()
+        });
+        checkpoints.save(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut checkpoints = Checkpoints::default();
+        checkpoints.update(Checkpoint {
+            hash: HashFunction::Md5,
+            predicate: Predicate::LeadingZeroNibbles(7),
+            scanned: 1 << 20,
+            key: "a key with spaces".to_string(),
+        });
+        checkpoints.update(Checkpoint {
+            hash: HashFunction::Sha256,
+            predicate: Predicate::LeadingZeroBits(40),
+            scanned: 42,
+            key: "abc".to_string(),
+        });
+
+        let contents = checkpoints.to_string();
+        assert_eq!(
+            contents,
+            "md5 zeroes:7 1048576 a key with spaces\nsha256 bits:40 42 abc\n"
+        );
+        assert_eq!(Checkpoints::parse(&contents), Ok(checkpoints));
+        assert_eq!(
+            Checkpoints::parse("md5 zeroes:7 many abc"),
+            Err("Invalid checkpoint on line 1".to_string())
+        );
+    }
+
+    #[test]
+    fn resume() {
+        let mut checkpoints = Checkpoints::default();
+        let predicate = Predicate::LeadingZeroNibbles(6);
+        let checkpoint = |scanned| Checkpoint {
+            hash: HashFunction::Md5,
+            predicate: predicate.clone(),
+            scanned,
+            key: "abc".to_string(),
+        };
+
+        checkpoints.update(checkpoint(100));
+        checkpoints.update(checkpoint(50));
+        assert_eq!(
+            checkpoints.resume(HashFunction::Md5, &predicate, "abc"),
+            100
+        );
+        assert_eq!(checkpoints.resume(HashFunction::Sha1, &predicate, "abc"), 0);
+        assert_eq!(checkpoints.resume(HashFunction::Md5, &predicate, "abd"), 0);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(185)), "3m05s");
+        assert_eq!(format_duration(Duration::from_secs(7500)), "2h05m");
+    }
+}
@@ -0,0 +1,302 @@
+use std::thread;
+
+use crate::rules::Rule;
+
+/// The primitives of a rule, each of which tracks one fact about the bytes
+/// seen so far.
+#[derive(Debug)]
+enum Detector {
+    Count {
+        members: Box<[bool; 256]>,
+        needed: usize,
+    },
+    Repeat(usize),
+    /// A KMP automaton that stays in `accept` once the needle was found.
+    Find {
+        table: Vec<[usize; 256]>,
+        accept: usize,
+    },
+    PairTwice,
+}
+
+impl Detector {
+    fn find(needle: &[u8]) -> Self {
+        let accept = needle.len();
+        let mut table = vec![[accept; 256]; accept + 1];
+
+        if let Some(first) = needle.first() {
+            table[0] = [0; 256];
+            table[0][*first as usize] = 1;
+
+            let mut fallback = 0;
+            for (state, byte) in needle.iter().enumerate().skip(1) {
+                table[state] = table[fallback];
+                table[state][*byte as usize] = state + 1;
+                fallback = table[fallback][*byte as usize];
+            }
+        }
+
+        Detector::Find { table, accept }
+    }
+}
+
+/// How the detectors combine into the outcome of the rule.
+#[derive(Debug)]
+enum Node {
+    Detector(usize),
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    Not(Box<Node>),
+}
+
+impl Node {
+    fn eval(&self, found: &[bool]) -> bool {
+        match self {
+            Node::Detector(idx) => found[*idx],
+            Node::And(nodes) => nodes.iter().all(|node| node.eval(found)),
+            Node::Or(nodes) => nodes.iter().any(|node| node.eval(found)),
+            Node::Not(node) => !node.eval(found),
+        }
+    }
+}
+
+/// The state of one evaluation, kept between lines so that a thread doesn't
+/// allocate per line.
+pub struct Scratch {
+    found: Vec<bool>,
+    counts: Vec<usize>,
+    states: Vec<usize>,
+    /// For every pair of bytes, the line it was last seen on and where it
+    /// was first seen on that line. Lines are told apart by `line`, so the
+    /// table never has to be cleared.
+    pairs: Vec<(u32, u32)>,
+    line: u32,
+}
+
+/// A rule compiled so that a single pass over the bytes of a line advances
+/// all of its primitives at once.
+#[derive(Debug)]
+pub struct Automaton {
+    detectors: Vec<Detector>,
+    root: Node,
+}
+
+impl Automaton {
+    pub fn compile(rule: &Rule) -> Self {
+        let mut detectors = Vec::new();
+        let root = Self::compile_node(rule, &mut detectors);
+
+        Automaton { detectors, root }
+    }
+
+    fn compile_node(rule: &Rule, detectors: &mut Vec<Detector>) -> Node {
+        let detector = match rule {
+            Rule::AtLeast { count, set } => {
+                let mut members = [false; 256];
+                for byte in set.bytes() {
+                    members[byte as usize] = true;
+                }
+                Detector::Count {
+                    members: Box::new(members),
+                    needed: *count,
+                }
+            }
+            Rule::Repeat(distance) => Detector::Repeat(*distance),
+            Rule::Contains(needle) => Detector::find(needle.as_bytes()),
+            Rule::PairTwice => Detector::PairTwice,
+            Rule::And(rules) => {
+                return Node::And(
+                    rules
+                        .iter()
+                        .map(|rule| Self::compile_node(rule, detectors))
+                        .collect(),
+                )
+            }
+            Rule::Or(rules) => {
+                return Node::Or(
+                    rules
+                        .iter()
+                        .map(|rule| Self::compile_node(rule, detectors))
+                        .collect(),
+                )
+            }
+            Rule::Not(rule) => return Node::Not(Box::new(Self::compile_node(rule, detectors))),
+        };
+
+        detectors.push(detector);
+        Node::Detector(detectors.len() - 1)
+    }
+
+    pub fn scratch(&self) -> Scratch {
+        let pairs = self
+            .detectors
+            .iter()
+            .any(|detector| matches!(detector, Detector::PairTwice));
+
+        Scratch {
+            found: vec![false; self.detectors.len()],
+            counts: vec![0; self.detectors.len()],
+            states: vec![0; self.detectors.len()],
+            pairs: if pairs {
+                vec![(0, 0); 1 << 16]
+            } else {
+                Vec::new()
+            },
+            line: 0,
+        }
+    }
+
+    pub fn matches(&self, line: &[u8], scratch: &mut Scratch) -> bool {
+        scratch.found.iter_mut().for_each(|found| *found = false);
+        scratch.counts.iter_mut().for_each(|count| *count = 0);
+        scratch.states.iter_mut().for_each(|state| *state = 0);
+        scratch.line = scratch.line.wrapping_add(1);
+        if scratch.line == 0 {
+            scratch.pairs.iter_mut().for_each(|pair| *pair = (0, 0));
+            scratch.line = 1;
+        }
+
+        for (idx, byte) in line.iter().enumerate() {
+            for (k, detector) in self.detectors.iter().enumerate() {
+                match detector {
+                    Detector::Count { members, .. } => {
+                        scratch.counts[k] += members[*byte as usize] as usize
+                    }
+                    Detector::Repeat(distance) => {
+                        if idx >= *distance && line[idx - distance] == *byte {
+                            scratch.found[k] = true;
+                        }
+                    }
+                    Detector::Find { table, .. } => {
+                        scratch.states[k] = table[scratch.states[k]][*byte as usize];
+                    }
+                    Detector::PairTwice if idx > 0 => {
+                        let pair = (line[idx - 1] as usize) << 8 | *byte as usize;
+                        let (seen_on, first) = &mut scratch.pairs[pair];
+                        if *seen_on != scratch.line {
+                            *seen_on = scratch.line;
+                            *first = idx as u32;
+                        } else if idx >= *first as usize + 2 {
+                            scratch.found[k] = true;
+                        }
+                    }
+                    Detector::PairTwice => {}
+                }
+            }
+        }
+
+        for (k, detector) in self.detectors.iter().enumerate() {
+            match detector {
+                Detector::Count { needed, .. } => scratch.found[k] = scratch.counts[k] >= *needed,
+                Detector::Find { accept, .. } => scratch.found[k] = scratch.states[k] == *accept,
+                _ => {}
+            }
+        }
+
+        self.root.eval(&scratch.found)
+    }
+
+    /// Counts the matching lines of `input`, which is split into one part
+    /// per thread at line boundaries.
+    pub fn count(&self, input: &[u8], threads: usize) -> usize {
+        thread::scope(|scope| {
+            let handles: Vec<_> = split(input, threads)
+                .into_iter()
+                .map(|part| {
+                    scope.spawn(move || {
+                        let mut scratch = self.scratch();
+                        lines(part)
+                            .filter(|line| self.matches(line, &mut scratch))
+                            .count()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum()
+        })
+    }
+}
+
+/// The lines of `input` without their line endings, like `BufRead::lines`.
+pub fn lines(input: &[u8]) -> impl Iterator<Item = &[u8]> {
+    input.split_inclusive(|byte| *byte == b'\n').map(|line| {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        line.strip_suffix(b"\r").unwrap_or(line)
+    })
+}
+
+/// Cuts `input` into at most `parts` pieces of about the same size, each
+/// ending at the end of a line.
+fn split(input: &[u8], parts: usize) -> Vec<&[u8]> {
+    let size = input.len().div_ceil(parts.max(1)).max(1);
+    let mut pieces = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let end = match rest.iter().skip(size - 1).position(|byte| *byte == b'\n') {
+            Some(idx) => idx + size,
+            None => rest.len(),
+        };
+        let (piece, tail) = rest.split_at(end.min(rest.len()));
+        pieces.push(piece);
+        rest = tail;
+    }
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Policies;
+    use proptest::prelude::*;
+
+    #[test]
+    fn splits_at_lines() {
+        let input = b"abc\ndef\r\n\nghij\nk";
+
+        assert_eq!(
+            lines(input).collect::<Vec<_>>(),
+            vec![&b"abc"[..], b"def", b"", b"ghij", b"k"]
+        );
+        for parts in 1..8 {
+            let pieces = split(input, parts);
+            assert_eq!(pieces.concat(), input.to_vec());
+            assert!(pieces.len() <= parts);
+            assert!(pieces[..pieces.len() - 1]
+                .iter()
+                .all(|p| p.ends_with(b"\n")));
+        }
+    }
+
+    #[test]
+    fn finds_needles() {
+        let automaton = Automaton::compile(&Rule::contains("abab"));
+        let mut scratch = automaton.scratch();
+        assert!(automaton.matches(b"aabaabab", &mut scratch));
+        assert!(!automaton.matches(b"abaaba", &mut scratch));
+
+        let automaton = Automaton::compile(&Rule::contains("abab").or(Rule::contains("")));
+        assert!(automaton.matches(b"", &mut automaton.scratch()));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+        #[test]
+        fn agrees_with_rules(words in prop::collection::vec("[abcdeixy]{0,12}", 1..20)) {
+            let policies = Policies::builtin()
+                .load(r#"odd = or(not(part-a), and(contains("aba"), at-least(2, "xy"), repeat(3)))"#)
+                .unwrap();
+            let input: String = words.iter().map(|word| format!("{}\n", word)).collect();
+
+            for name in policies.names() {
+                let rule = policies.get(name).unwrap();
+                let expected = words.iter().filter(|word| rule.matches(word)).count();
+                prop_assert_eq!(Automaton::compile(rule).count(input.as_bytes(), 3), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::thread;
+
+use aoc_2015::cli::Args;
+use aoc_2015::io::read_bytes;
+use aoc_2015::logging;
+use aoc_2015::variants::Implementations;
+use aoc_2015::Answer;
+
+mod automaton;
+mod rules;
+mod verdict;
+
+use automaton::Automaton;
+use rules::{Policies, Rule};
+
+/// Counts the lines of the input that satisfy a rule, using the given
+/// number of threads.
+type Counter = fn(&Rule, &[u8], usize) -> usize;
+
+fn count_compiled(rule: &Rule, input: &[u8], threads: usize) -> usize {
+    Automaton::compile(rule).count(input, threads)
+}
+
+fn count_per_rule(rule: &Rule, input: &[u8], _threads: usize) -> usize {
+    automaton::lines(input)
+        .filter(|line| rule.matches(line))
+        .count()
+}
+
+fn implementations() -> Implementations<Counter> {
+    Implementations::new("automaton", count_compiled as Counter).register("rules", count_per_rule)
+}
+
+fn policy<'a>(policies: &'a Policies, name: &str) -> Result<&'a Rule, String> {
+    policies.get(name).ok_or_else(|| {
+        format!(
+            "Unknown policy '{}', expected one of {}",
+            name,
+            policies.names().join(", ")
+        )
+    })
+}
+
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+
+    // `--policies FILE` adds the policies in `FILE` to the built-in ones.
+    let policies = match args.value("policies") {
+        Some(path) => {
+            let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+            Policies::builtin()
+                .load(&source)
+                .map_err(|e| format!("{}: {}", path, e))?
+        }
+        None => Policies::builtin(),
+    };
+
+    let count = implementations().select(args.value("impl"))?;
+    let threads = match args.value("threads").map(str::parse) {
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+        Some(Ok(threads)) if threads > 0 => threads,
+        Some(_) => return Err("Invalid number of threads".to_string()),
+    };
+
+    let input = logging::read(read_bytes);
+    let count_nice = |rule| Answer::from(count(rule, &input, threads));
+
+    let part_a = policy(&policies, "part-a")?;
+    let part_b = policy(&policies, "part-b")?;
+    let solution_a = logging::solve("a", || count_nice(part_a));
+    let solution_b = logging::solve("b", || count_nice(part_b));
+
+    println!("{}", solution_a);
+    println!("{}", solution_b);
+
+    if let Some(name) = args.value("policy") {
+        let rule = policy(&policies, name)?;
+        let solution = logging::solve("policy", || count_nice(rule));
+
+        println!("{}", solution);
+    }
+
+    // `--explain table|json` dumps the verdict of every string under the
+    // policy from `--policy`, or both parts, to stdout or `--output PATH`.
+    if let Some(format) = args.value("explain") {
+        let explain = match format {
+            "table" => verdict::to_table,
+            "json" => verdict::to_json,
+            _ => return Err(format!("Unknown explain format '{}'", format)),
+        };
+
+        let names = match args.value("policy") {
+            Some(name) => vec![name],
+            None => vec!["part-a", "part-b"],
+        };
+        let named = names
+            .into_iter()
+            .map(|name| policy(&policies, name).map(|rule| (name, rule)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let data: Vec<_> = automaton::lines(&input).collect();
+        let output = explain(&named, &data);
+
+        match args.value("output") {
+            Some(path) => fs::write(path, output).map_err(|e| e.to_string())?,
+            None => print!("{}", output),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples() {
+        let input = b"ugknbfddgicrmopn\naaa\njchzalrnumimnmhp\nqjhvhtzxzqqjkmpb\nxxyxx\n";
+        let policies = Policies::builtin();
+
+        implementations().assert_agree(|count| {
+            (
+                count(policies.get("part-a").unwrap(), input, 2),
+                count(policies.get("part-b").unwrap(), input, 2),
+            )
+        });
+        assert_eq!(count_compiled(policies.get("part-a").unwrap(), input, 2), 2);
+        assert_eq!(count_compiled(policies.get("part-b").unwrap(), input, 2), 2);
+
+        // Invalid UTF-8 is matched byte by byte, not as replacement characters.
+        let input = b"\xff\xff\n\xfe\xff\xfe\n";
+        let rule = Rule::repeat(3);
+        implementations().assert_agree(|count| count(&rule, input, 2));
+        assert_eq!(count_per_rule(&rule, input, 2), 0);
+        assert_eq!(count_per_rule(&Rule::repeat(2), input, 2), 1);
+    }
+}
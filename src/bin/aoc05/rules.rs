@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Not;
+
+/// A condition on a string, built from a few primitives.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Rule {
+    /// At least `count` of the bytes are in `set`.
+    AtLeast {
+        count: usize,
+        set: String,
+    },
+    /// Some byte is equal to the one `distance` positions further on.
+    Repeat(usize),
+    Contains(String),
+    /// Some pair of bytes occurs twice without the two overlapping.
+    PairTwice,
+    And(Vec<Rule>),
+    Or(Vec<Rule>),
+    Not(Box<Rule>),
+}
+
+impl Rule {
+    pub fn at_least(count: usize, set: &str) -> Self {
+        Rule::AtLeast {
+            count,
+            set: set.to_string(),
+        }
+    }
+
+    /// Panics if `distance` is zero, which the parser rejects as well.
+    pub fn repeat(distance: usize) -> Self {
+        assert!(distance > 0, "repeat distance must be positive");
+        Rule::Repeat(distance)
+    }
+
+    pub fn contains(needle: &str) -> Self {
+        Rule::Contains(needle.to_string())
+    }
+
+    pub fn pair_twice() -> Self {
+        Rule::PairTwice
+    }
+
+    pub fn and(self, other: Rule) -> Self {
+        match self {
+            Rule::And(mut rules) => {
+                rules.push(other);
+                Rule::And(rules)
+            }
+            rule => Rule::And(vec![rule, other]),
+        }
+    }
+
+    pub fn or(self, other: Rule) -> Self {
+        match self {
+            Rule::Or(mut rules) => {
+                rules.push(other);
+                Rule::Or(rules)
+            }
+            rule => Rule::Or(vec![rule, other]),
+        }
+    }
+
+    /// Whether the rule holds for `s`, looking at its raw bytes like the
+    /// compiled automaton does.
+    pub fn matches<S: AsRef<[u8]> + ?Sized>(&self, s: &S) -> bool {
+        self.matches_bytes(s.as_ref())
+    }
+
+    fn matches_bytes(&self, bytes: &[u8]) -> bool {
+        match self {
+            Rule::AtLeast { count, set } => {
+                bytes.iter().filter(|b| set.as_bytes().contains(b)).count() >= *count
+            }
+            Rule::Repeat(distance) => bytes
+                .iter()
+                .zip(bytes.iter().skip(*distance))
+                .any(|(a, b)| a == b),
+            Rule::Contains(needle) => {
+                let needle = needle.as_bytes();
+                needle.is_empty() || bytes.windows(needle.len()).any(|w| w == needle)
+            }
+            Rule::PairTwice => {
+                let mut first = HashMap::new();
+                bytes
+                    .windows(2)
+                    .enumerate()
+                    .any(|(idx, pair)| idx >= *first.entry(pair).or_insert(idx) + 2)
+            }
+            Rule::And(rules) => rules.iter().all(|rule| rule.matches_bytes(bytes)),
+            Rule::Or(rules) => rules.iter().any(|rule| rule.matches_bytes(bytes)),
+            Rule::Not(rule) => !rule.matches_bytes(bytes),
+        }
+    }
+}
+
+impl Not for Rule {
+    type Output = Rule;
+
+    fn not(self) -> Rule {
+        Rule::Not(Box::new(self))
+    }
+}
+
+/// Quotes a string so that `tokenize` reads it back unchanged.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, name: &str, rules: &[Rule]) -> fmt::Result {
+    write!(f, "{}(", name)?;
+    for (idx, rule) in rules.iter().enumerate() {
+        if idx > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", rule)?;
+    }
+    write!(f, ")")
+}
+
+/// Writes the rule in the language `Policies::load` reads.
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::AtLeast { count, set } => write!(f, "at-least({}, {})", count, quote(set)),
+            Rule::Repeat(distance) => write!(f, "repeat({})", distance),
+            Rule::Contains(needle) => write!(f, "contains({})", quote(needle)),
+            Rule::PairTwice => write!(f, "pair-twice()"),
+            Rule::And(rules) => write_list(f, "and", rules),
+            Rule::Or(rules) => write_list(f, "or", rules),
+            Rule::Not(rule) => write!(f, "not({})", rule),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseRuleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Name(String),
+    Number(usize),
+    Str(String),
+    Open,
+    Close,
+    Comma,
+    Equals,
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => break,
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            ',' => tokens.push(Token::Comma),
+            '=' => tokens.push(Token::Equals),
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => s.extend(chars.next()),
+                        Some(c) => s.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            c if c.is_ascii_digit() => {
+                let mut digits = c.to_string();
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(c);
+                }
+                let number = digits
+                    .parse()
+                    .map_err(|_| format!("number '{}' is too large", digits))?;
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut name = c.to_string();
+                while let Some(c) =
+                    chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+                {
+                    name.push(c);
+                }
+                tokens.push(Token::Name(name));
+            }
+            c if c.is_whitespace() => {}
+            c => return Err(format!("unexpected character '{}'", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads one rule from the tokens of a definition, resolving the names of
+/// policies defined before it.
+struct Parser<'a> {
+    tokens: std::vec::IntoIter<Token>,
+    policies: &'a Policies,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Result<Token, String> {
+        self.tokens
+            .next()
+            .ok_or_else(|| "unexpected end of line".to_string())
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), String> {
+        if self.next()? == expected {
+            Ok(())
+        } else {
+            Err(format!("expected {}", description))
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        match self.next()? {
+            Token::Number(n) => Ok(n),
+            _ => Err("expected a number".to_string()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Str(s) => Ok(s),
+            _ => Err("expected a string".to_string()),
+        }
+    }
+
+    fn rules(&mut self) -> Result<Vec<Rule>, String> {
+        let mut rules = vec![self.rule()?];
+        loop {
+            match self.next()? {
+                Token::Comma => rules.push(self.rule()?),
+                Token::Close => return Ok(rules),
+                _ => return Err("expected ',' or ')'".to_string()),
+            }
+        }
+    }
+
+    fn rule(&mut self) -> Result<Rule, String> {
+        let name = match self.next()? {
+            Token::Name(name) => name,
+            _ => return Err("expected a rule".to_string()),
+        };
+
+        if self.tokens.as_slice().first() != Some(&Token::Open) {
+            return self
+                .policies
+                .get(&name)
+                .cloned()
+                .ok_or_else(|| format!("unknown policy '{}'", name));
+        }
+        self.next()?;
+
+        let close = |parser: &mut Self, rule| {
+            parser.expect(Token::Close, "')'")?;
+            Ok(rule)
+        };
+
+        match name.as_str() {
+            "at-least" => {
+                let count = self.number()?;
+                self.expect(Token::Comma, "','")?;
+                let set = self.string()?;
+                close(self, Rule::AtLeast { count, set })
+            }
+            "repeat" => {
+                let distance = self.number()?;
+                if distance == 0 {
+                    return Err("repeat distance must be positive".to_string());
+                }
+                close(self, Rule::Repeat(distance))
+            }
+            "contains" => {
+                let needle = self.string()?;
+                close(self, Rule::Contains(needle))
+            }
+            "pair-twice" => close(self, Rule::PairTwice),
+            "and" => Ok(Rule::And(self.rules()?)),
+            "or" => Ok(Rule::Or(self.rules()?)),
+            "not" => {
+                let rule = self.rule()?;
+                close(self, !rule)
+            }
+            _ => Err(format!("unknown rule '{}'", name)),
+        }
+    }
+}
+
+/// Rules that have been given a name, so they can be selected on the
+/// command line and used in other policies.
+#[derive(Debug, Default, Clone)]
+pub struct Policies {
+    entries: Vec<(String, Rule)>,
+}
+
+impl Policies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The rules of both parts of the puzzle.
+    pub fn builtin() -> Self {
+        let naughty = Rule::contains("ab")
+            .or(Rule::contains("cd"))
+            .or(Rule::contains("pq"))
+            .or(Rule::contains("xy"));
+
+        Policies::new()
+            .define(
+                "part-a",
+                Rule::at_least(3, "aeiou")
+                    .and(Rule::repeat(1))
+                    .and(!naughty),
+            )
+            .define("part-b", Rule::pair_twice().and(Rule::repeat(2)))
+    }
+
+    /// Adds a policy, replacing any earlier one with the same name.
+    pub fn define(mut self, name: &str, rule: Rule) -> Self {
+        match self.entries.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = rule,
+            None => self.entries.push((name.to_string(), rule)),
+        }
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Rule> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, rule)| rule)
+    }
+
+    /// Adds the policies in `source`, one `name = rule` per line. A rule is
+    /// one of the primitives
+    ///
+    /// ```text
+    /// at-least(3, "aeiou")   repeat(1)   contains("ab")   pair-twice()
+    /// ```
+    ///
+    /// combined with `and(...)`, `or(...)` and `not(...)`, or the name of a
+    /// policy defined earlier. Everything after a `#` is a comment.
+    pub fn load(self, source: &str) -> Result<Self, ParseRuleError> {
+        source
+            .lines()
+            .enumerate()
+            .try_fold(self, |policies, (idx, line)| {
+                let error = |message| ParseRuleError {
+                    line: idx + 1,
+                    message,
+                };
+
+                let mut tokens = tokenize(line).map_err(error)?.into_iter();
+                let name = match (tokens.next(), tokens.next()) {
+                    (None, _) => return Ok(policies),
+                    (Some(Token::Name(name)), Some(Token::Equals)) => name,
+                    _ => return Err(error("expected 'name = rule'".to_string())),
+                };
+
+                let mut parser = Parser {
+                    tokens,
+                    policies: &policies,
+                };
+                let rule = parser.rule().map_err(error)?;
+                if parser.tokens.next().is_some() {
+                    return Err(error("unexpected input after the rule".to_string()));
+                }
+
+                Ok(policies.define(&name, rule))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives() {
+        assert!(Rule::at_least(3, "aeiou").matches("xazegov"));
+        assert!(!Rule::at_least(3, "aeiou").matches("dvszwmarrgswjxmb"));
+        assert!(Rule::repeat(1).matches("abcdde"));
+        assert!(Rule::repeat(2).matches("efe"));
+        assert!(!Rule::repeat(2).matches("eef"));
+        assert!(Rule::pair_twice().matches("aabcdefgaa"));
+        assert!(Rule::pair_twice().matches("xyxy"));
+        assert!(!Rule::pair_twice().matches("aaa"));
+        assert!(!Rule::pair_twice().matches(""));
+    }
+
+    #[test]
+    #[should_panic(expected = "repeat distance must be positive")]
+    fn repeat_zero() {
+        Rule::repeat(0);
+    }
+
+    #[test]
+    fn builtin_policies() {
+        let policies = Policies::builtin();
+        let part_a = policies.get("part-a").unwrap();
+        let part_b = policies.get("part-b").unwrap();
+
+        assert!(part_a.matches("ugknbfddgicrmopn"));
+        assert!(part_a.matches("aaa"));
+        assert!(!part_a.matches("jchzalrnumimnmhp"));
+        assert!(!part_a.matches("haegwjzuvuyypxyu"));
+        assert!(!part_a.matches("dvszwmarrgswjxmb"));
+
+        assert!(part_b.matches("qjhvhtzxzqqjkmpb"));
+        assert!(part_b.matches("xxyxx"));
+        assert!(!part_b.matches("uurcxstgmygtbstg"));
+        assert!(!part_b.matches("ieodomkazucvgmuy"));
+    }
+
+    #[test]
+    fn load() {
+        let source = r#"
+            # Part one, written out.
+            vowels = at-least(3, "aeiou")
+            nice = and(vowels, repeat(1), not(or(contains("ab"), contains("cd"), contains("pq"), contains("xy"))))
+            strict = and(part-b, not(contains("\"")))
+        "#;
+        let policies = Policies::builtin().load(source).unwrap();
+
+        assert_eq!(policies.get("nice"), policies.get("part-a"));
+        assert_eq!(
+            policies.names(),
+            vec!["part-a", "part-b", "vowels", "nice", "strict"]
+        );
+
+        let strict = policies.get("strict").unwrap();
+        assert_eq!(
+            strict.to_string(),
+            r#"and(and(pair-twice(), repeat(2)), not(contains("\"")))"#
+        );
+        assert!(!strict.matches("xxyxx\""));
+
+        let reloaded = Policies::new()
+            .load(&format!("strict = {}", strict))
+            .unwrap();
+        assert_eq!(reloaded.get("strict"), Some(strict));
+    }
+
+    #[test]
+    fn load_errors() {
+        let error = |source: &str| Policies::builtin().load(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error("a = repeat(1)\nb = nice"),
+            "line 2: unknown policy 'nice'"
+        );
+        assert_eq!(error("a = maybe(1)"), "line 1: unknown rule 'maybe'");
+        assert_eq!(error("a = repeat(1"), "line 1: unexpected end of line");
+        assert_eq!(error("a = at-least(3)"), "line 1: expected ','");
+        assert_eq!(error("a = contains(\"ab)"), "line 1: unterminated string");
+        assert_eq!(error("repeat(1)"), "line 1: expected 'name = rule'");
+        assert_eq!(
+            error("a = repeat(1) repeat(2)"),
+            "line 1: unexpected input after the rule"
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::rules::Rule;
+
+/// What a rule found in a string. Positions are 1-based.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Evidence {
+    /// Every byte from the set, with its position.
+    Counted(Vec<(usize, char)>),
+    /// Where a repeated byte or a needle was first found.
+    At(usize),
+    /// The positions of the two occurrences of a pair.
+    Pair(usize, usize),
+    /// Nothing was found, or the rule combines other rules.
+    Nothing,
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Evidence::Counted(found) if found.is_empty() => write!(f, "found none"),
+            Evidence::Counted(found) => {
+                let found: Vec<_> = found
+                    .iter()
+                    .map(|(position, c)| format!("{}@{}", c, position))
+                    .collect();
+                write!(f, "found {}", found.join(" "))
+            }
+            Evidence::At(position) => write!(f, "at {}", position),
+            Evidence::Pair(first, second) => write!(f, "at {} and {}", first, second),
+            Evidence::Nothing => Ok(()),
+        }
+    }
+}
+
+/// The outcome of a rule on one string, with the outcomes of the rules it
+/// combines.
+#[derive(Debug, PartialEq)]
+pub struct Verdict<'a> {
+    pub rule: &'a Rule,
+    pub passed: bool,
+    pub evidence: Evidence,
+    pub children: Vec<Verdict<'a>>,
+}
+
+impl Rule {
+    /// Like `matches`, but evaluates every rule instead of stopping at the
+    /// first decisive one, and records what each of them found. Positions
+    /// count raw bytes, like `matches` does.
+    pub fn classify<S: AsRef<[u8]> + ?Sized>(&self, s: &S) -> Verdict<'_> {
+        self.classify_bytes(s.as_ref())
+    }
+
+    fn classify_bytes(&self, bytes: &[u8]) -> Verdict<'_> {
+        let verdict = |passed, evidence| Verdict {
+            rule: self,
+            passed,
+            evidence,
+            children: Vec::new(),
+        };
+
+        match self {
+            Rule::AtLeast { count, set } => {
+                let found: Vec<_> = bytes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, b)| set.as_bytes().contains(b))
+                    .map(|(idx, b)| (idx + 1, *b as char))
+                    .collect();
+                verdict(found.len() >= *count, Evidence::Counted(found))
+            }
+            Rule::Repeat(distance) => {
+                match bytes
+                    .iter()
+                    .zip(bytes.iter().skip(*distance))
+                    .position(|(a, b)| a == b)
+                {
+                    Some(idx) => verdict(true, Evidence::At(idx + 1)),
+                    None => verdict(false, Evidence::Nothing),
+                }
+            }
+            Rule::Contains(needle) => {
+                let needle = needle.as_bytes();
+                let found = if needle.is_empty() {
+                    Some(0)
+                } else {
+                    bytes.windows(needle.len()).position(|w| w == needle)
+                };
+                match found {
+                    Some(idx) => verdict(true, Evidence::At(idx + 1)),
+                    None => verdict(false, Evidence::Nothing),
+                }
+            }
+            Rule::PairTwice => {
+                let mut first = HashMap::new();
+                let found = bytes.windows(2).enumerate().find_map(|(idx, pair)| {
+                    let first = *first.entry(pair).or_insert(idx);
+                    Some(Evidence::Pair(first + 1, idx + 1)).filter(|_| idx >= first + 2)
+                });
+                verdict(found.is_some(), found.unwrap_or(Evidence::Nothing))
+            }
+            Rule::And(rules) | Rule::Or(rules) => {
+                let children: Vec<_> = rules
+                    .iter()
+                    .map(|rule| rule.classify_bytes(bytes))
+                    .collect();
+                let passed = match self {
+                    Rule::And(_) => children.iter().all(|child| child.passed),
+                    _ => children.iter().any(|child| child.passed),
+                };
+                Verdict {
+                    children,
+                    ..verdict(passed, Evidence::Nothing)
+                }
+            }
+            Rule::Not(rule) => {
+                let child = rule.classify_bytes(bytes);
+                Verdict {
+                    passed: !child.passed,
+                    children: vec![child],
+                    ..verdict(false, Evidence::Nothing)
+                }
+            }
+        }
+    }
+}
+
+impl Verdict<'_> {
+    /// How the rule is shown in a verdict: primitives in full, combinations
+    /// by their name since their parts follow below them.
+    fn label(&self) -> String {
+        match self.rule {
+            Rule::And(_) => "and".to_string(),
+            Rule::Or(_) => "or".to_string(),
+            Rule::Not(_) => "not".to_string(),
+            rule => rule.to_string(),
+        }
+    }
+
+    fn write_rows(&self, out: &mut String, depth: usize) {
+        let evidence = self.evidence.to_string();
+        out.push_str(&format!(
+            "{:indent$}{} {}{}{}\n",
+            "",
+            if self.passed { "pass" } else { "fail" },
+            self.label(),
+            if evidence.is_empty() { "" } else { "  " },
+            evidence,
+            indent = 2 * depth
+        ));
+
+        for child in &self.children {
+            child.write_rows(out, depth + 1);
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let evidence = match &self.evidence {
+            Evidence::Counted(found) => format!(
+                "{{\"found\":[{}]}}",
+                found
+                    .iter()
+                    .map(|(position, c)| format!(
+                        "{{\"position\":{},\"char\":{}}}",
+                        position,
+                        json_string(&c.to_string())
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            Evidence::At(position) => format!("{{\"position\":{}}}", position),
+            Evidence::Pair(first, second) => format!("{{\"positions\":[{},{}]}}", first, second),
+            Evidence::Nothing => "null".to_string(),
+        };
+
+        format!(
+            "{{\"rule\":{},\"passed\":{},\"evidence\":{},\"children\":[{}]}}",
+            json_string(&self.label()),
+            self.passed,
+            evidence,
+            self.children
+                .iter()
+                .map(Verdict::to_json)
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Lists the verdict of every string under each policy, with the rules
+/// indented below it. Strings are only decoded to be printed.
+pub fn to_table(policies: &[(&str, &Rule)], data: &[&[u8]]) -> String {
+    let mut out = String::new();
+
+    for (policy, rule) in policies {
+        for s in data {
+            let verdict = rule.classify(s);
+            out.push_str(&format!(
+                "{}: {} by {}\n",
+                String::from_utf8_lossy(s),
+                if verdict.passed { "nice" } else { "naughty" },
+                policy
+            ));
+            if verdict.children.is_empty() {
+                verdict.write_rows(&mut out, 1);
+            }
+            for child in &verdict.children {
+                child.write_rows(&mut out, 1);
+            }
+        }
+    }
+
+    out
+}
+
+/// A single JSON array with one object per string and policy, holding its
+/// verdict as a tree.
+pub fn to_json(policies: &[(&str, &Rule)], data: &[&[u8]]) -> String {
+    let entries: Vec<_> = policies
+        .iter()
+        .flat_map(|(policy, rule)| {
+            data.iter().map(move |s| {
+                format!(
+                    "{{\"string\":{},\"policy\":{},\"verdict\":{}}}",
+                    json_string(&String::from_utf8_lossy(s)),
+                    json_string(policy),
+                    rule.classify(s).to_json()
+                )
+            })
+        })
+        .collect();
+
+    format!("[{}]\n", entries.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Policies;
+    use json::ast::Json;
+    use json::decoder::Decoder;
+
+    #[test]
+    fn evidence() {
+        let policies = Policies::builtin();
+        let part_a = policies.get("part-a").unwrap();
+        let verdict = part_a.classify("haegwjzuvuyypxyu");
+
+        assert!(!verdict.passed);
+        let evidence: Vec<_> = verdict.children.iter().map(|c| &c.evidence).collect();
+        assert_eq!(
+            evidence,
+            vec![
+                &Evidence::Counted(vec![(2, 'a'), (3, 'e'), (8, 'u'), (10, 'u'), (16, 'u')]),
+                &Evidence::At(11),
+                &Evidence::Nothing,
+            ]
+        );
+
+        let forbidden = &verdict.children[2].children[0];
+        assert!(forbidden.passed);
+        let found: Vec<_> = forbidden.children.iter().map(|c| &c.evidence).collect();
+        assert_eq!(
+            found,
+            vec![
+                &Evidence::Nothing,
+                &Evidence::Nothing,
+                &Evidence::Nothing,
+                &Evidence::At(14)
+            ]
+        );
+
+        let part_b = policies.get("part-b").unwrap();
+        let verdict = part_b.classify("qjhvhtzxzqqjkmpb");
+        assert!(verdict.passed);
+        assert_eq!(verdict.children[0].evidence, Evidence::Pair(1, 11));
+        assert_eq!(verdict.children[1].evidence, Evidence::At(3));
+    }
+
+    #[test]
+    fn agrees_with_matches() {
+        let policies = Policies::builtin();
+
+        for name in policies.names() {
+            let rule = policies.get(name).unwrap();
+            for s in &[
+                "ugknbfddgicrmopn",
+                "aaa",
+                "jchzalrnumimnmhp",
+                "xxyxx",
+                "aaaa",
+                "",
+            ] {
+                assert_eq!(
+                    rule.classify(s).passed,
+                    rule.matches(s),
+                    "{} on {}",
+                    name,
+                    s
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn table() {
+        let policies = Policies::builtin();
+        let data: Vec<&[u8]> = vec![b"ieodomkazucvgmuy"];
+
+        assert_eq!(
+            to_table(&[("part-b", policies.get("part-b").unwrap())], &data),
+            "ieodomkazucvgmuy: naughty by part-b\n  fail pair-twice()\n  pass repeat(2)  at 3\n"
+        );
+        assert_eq!(
+            to_json(&[("part-b", policies.get("part-b").unwrap())], &data),
+            "[{\"string\":\"ieodomkazucvgmuy\",\"policy\":\"part-b\",\"verdict\":\
+             {\"rule\":\"and\",\"passed\":false,\"evidence\":null,\"children\":[\
+             {\"rule\":\"pair-twice()\",\"passed\":false,\"evidence\":null,\"children\":[]},\
+             {\"rule\":\"repeat(2)\",\"passed\":true,\"evidence\":{\"position\":3},\"children\":[]}]}}]\n"
+        );
+    }
+
+    #[test]
+    fn raw_bytes() {
+        let rule = Rule::repeat(3).and(Rule::contains("\u{fffd}"));
+        let data: Vec<&[u8]> = vec![b"\xff\xff"];
+
+        assert!(!rule.classify(data[0]).passed);
+        assert_eq!(
+            to_table(&[("r", &rule)], &data),
+            "\u{fffd}\u{fffd}: naughty by r\n  fail repeat(3)\n  fail contains(\"\u{fffd}\")\n"
+        );
+        assert_eq!(
+            Rule::contains("b").classify("\u{e9}b").evidence,
+            Evidence::At(3)
+        );
+    }
+
+    #[test]
+    fn json_policies() {
+        let policies = Policies::builtin();
+        let named = [
+            ("part-a", policies.get("part-a").unwrap()),
+            ("part-b", policies.get("part-b").unwrap()),
+        ];
+        let data: Vec<&[u8]> = vec![b"aaa", b"xxyxx"];
+        let output = to_json(&named, &data);
+
+        let mut chars = output.chars();
+        let json = Decoder::default(&mut chars).decode().unwrap();
+        assert!(chars.all(char::is_whitespace));
+
+        let entries = match json {
+            Json::Array(entries) => entries,
+            json => panic!("expected an array, got {:?}", json),
+        };
+        let found: Vec<_> = entries
+            .iter()
+            .map(|entry| match entry {
+                Json::Object(fields) => (&fields["policy"], &fields["string"]),
+                entry => panic!("expected an object, got {:?}", entry),
+            })
+            .collect();
+        let string = |s: &str| Json::String(s.to_string());
+        assert_eq!(
+            found,
+            vec![
+                (&string("part-a"), &string("aaa")),
+                (&string("part-a"), &string("xxyxx")),
+                (&string("part-b"), &string("aaa")),
+                (&string("part-b"), &string("xxyxx")),
+            ]
+        );
+    }
+}
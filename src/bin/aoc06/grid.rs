@@ -0,0 +1,146 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Coordinate, Range};
+
+/// The most lights that are kept in memory one by one. Larger grids, up to
+/// `u32::MAX` by `u32::MAX`, can only be counted by the sweep.
+pub const MAX_CELLS: usize = 1 << 26;
+
+/// The size of a grid of lights, whose coordinates run from `0,0` up to
+/// `width - 1,height - 1`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LightGrid {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The grid from the puzzle.
+impl Default for LightGrid {
+    fn default() -> Self {
+        LightGrid {
+            width: 1000,
+            height: 1000,
+        }
+    }
+}
+
+impl LightGrid {
+    /// The number of lights.
+    pub fn cells(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Fails if the grid has too many lights for `what` to store them all.
+    pub fn check_cells(&self, what: &str) -> Result<(), String> {
+        if self.cells() > MAX_CELLS {
+            Err(format!("The {} grid is too large for {}", self, what))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Where a light is stored in a row-major vector of all lights.
+    pub fn index(&self, coordinate: Coordinate) -> usize {
+        coordinate.1 as usize * self.width as usize + coordinate.0 as usize
+    }
+
+    pub fn coordinates(&self) -> impl Iterator<Item = Coordinate> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Coordinate(x, y)))
+    }
+
+    pub fn contains(&self, coordinate: Coordinate) -> bool {
+        coordinate.0 < self.width && coordinate.1 < self.height
+    }
+
+    /// Swaps the corners of an inverted range so that the first one is the
+    /// top left, or returns the first corner that lies outside the grid.
+    pub fn normalize(&self, range: Range) -> Result<Range, Coordinate> {
+        let Range(a, b) = range;
+        if let Some(outside) = [a, b].iter().find(|corner| !self.contains(**corner)) {
+            return Err(*outside);
+        }
+
+        Ok(Range(
+            Coordinate(a.0.min(b.0), a.1.min(b.1)),
+            Coordinate(a.0.max(b.0), a.1.max(b.1)),
+        ))
+    }
+}
+
+/// Parses `WIDTHxHEIGHT`.
+impl FromStr for LightGrid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid grid size '{}', expected WIDTHxHEIGHT", s);
+        let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+
+        match (width.parse(), height.parse()) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok(LightGrid { width, height }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for LightGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "640x480".parse(),
+            Ok(LightGrid {
+                width: 640,
+                height: 480
+            })
+        );
+        assert!("640x0".parse::<LightGrid>().is_err());
+        assert!("640".parse::<LightGrid>().is_err());
+        assert_eq!(LightGrid::default().to_string(), "1000x1000");
+    }
+
+    #[test]
+    fn max_size() {
+        let grid: LightGrid = "4294967295x4294967295".parse().unwrap();
+
+        assert_eq!(grid.cells(), 18_446_744_065_119_617_025);
+        assert_eq!(
+            grid.index(Coordinate(u32::MAX - 1, u32::MAX - 1)),
+            grid.cells() - 1
+        );
+        assert_eq!(
+            grid.check_cells("the grid"),
+            Err("The 4294967295x4294967295 grid is too large for the grid".to_string())
+        );
+        assert!(LightGrid::default().check_cells("the grid").is_ok());
+        assert!("4294967296x1".parse::<LightGrid>().is_err());
+    }
+
+    #[test]
+    fn normalize() {
+        let grid = LightGrid {
+            width: 10,
+            height: 5,
+        };
+
+        assert_eq!(
+            grid.normalize(Range(Coordinate(7, 1), Coordinate(2, 4))),
+            Ok(Range(Coordinate(2, 1), Coordinate(7, 4)))
+        );
+        assert_eq!(
+            grid.normalize(Range(Coordinate(2, 1), Coordinate(9, 5))),
+            Err(Coordinate(9, 5))
+        );
+        assert_eq!(grid.coordinates().count(), grid.cells());
+        assert_eq!(grid.index(Coordinate(3, 2)), 23);
+    }
+}
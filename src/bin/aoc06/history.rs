@@ -0,0 +1,399 @@
+use std::mem;
+
+use crate::grid::LightGrid;
+use crate::model::{Brightness, LightModel, Switches};
+use crate::{Coordinate, Instruction, Range};
+
+/// The side of the square tiles that the instructions are indexed by.
+const TILE: u32 = 64;
+/// How many instructions apart the lights are saved at first, so that an
+/// edit in the past only replays the instructions since the last save.
+const SNAPSHOT_INTERVAL: usize = 64;
+/// How many bytes the saved lights may take up together. When they would
+/// take more, every other one is dropped and the interval doubles.
+const SNAPSHOT_MEMORY: usize = 1 << 28;
+
+/// Every light, both as on/off and as brightness.
+#[derive(Debug, Clone)]
+struct Lights {
+    on: Vec<bool>,
+    brightness: Vec<i32>,
+}
+
+impl Lights {
+    fn dark(grid: &LightGrid) -> Self {
+        Lights {
+            on: vec![false; grid.cells()],
+            brightness: vec![0; grid.cells()],
+        }
+    }
+
+    /// Applies an instruction and returns how much it changed the number of
+    /// lights that are on and the total brightness.
+    fn apply(&mut self, grid: &LightGrid, instruction: &Instruction) -> (i64, i64) {
+        let Range(start, end) = instruction.range;
+        let (mut lit, mut brightness) = (0, 0);
+
+        for y in start.1..=end.1 {
+            let row = grid.index(Coordinate(0, y));
+            for idx in row + start.0 as usize..=row + end.0 as usize {
+                let on = Switches.apply(instruction.action, self.on[idx]);
+                let level = Brightness.apply(instruction.action, self.brightness[idx]);
+                lit += on as i64 - self.on[idx] as i64;
+                brightness += (level - self.brightness[idx]) as i64;
+                self.on[idx] = on;
+                self.brightness[idx] = level;
+            }
+        }
+
+        (lit, brightness)
+    }
+}
+
+/// Indexes a list of instructions so that the grid can be queried at any
+/// step without replaying it, and edited like in an editor: instructions
+/// can be undone, redone, and a new one replaces everything that was undone.
+///
+/// Step `k` is the grid after the first `k` instructions, so step 0 is the
+/// dark grid and instructions are numbered from 1.
+#[derive(Debug)]
+pub struct History {
+    grid: LightGrid,
+    instructions: Vec<Instruction>,
+    /// How many instructions are applied. The ones after them were undone.
+    applied: usize,
+    /// The number of lights on and the total brightness after every step.
+    totals: Vec<(u64, i64)>,
+    /// For every tile, the instructions whose range overlaps it, in order.
+    tiles: Vec<Vec<usize>>,
+    columns: usize,
+    /// The lights after all instructions, including the undone ones.
+    lights: Lights,
+    /// The lights after every `interval`th step, leaving out the dark grid
+    /// at step 0.
+    snapshots: Vec<Lights>,
+    interval: usize,
+    /// How many snapshots fit in `SNAPSHOT_MEMORY`.
+    max_snapshots: usize,
+}
+
+impl History {
+    pub fn new(grid: &LightGrid, instructions: &[Instruction]) -> Result<Self, String> {
+        Self::with_memory(grid, instructions, SNAPSHOT_MEMORY)
+    }
+
+    /// Like `new`, with at most `memory` bytes of snapshots.
+    fn with_memory(
+        grid: &LightGrid,
+        instructions: &[Instruction],
+        memory: usize,
+    ) -> Result<Self, String> {
+        grid.check_cells("a history")?;
+
+        let columns = grid.width.div_ceil(TILE) as usize;
+        let rows = grid.height.div_ceil(TILE) as usize;
+        let lights = Lights::dark(grid);
+        let mut history = History {
+            grid: *grid,
+            instructions: Vec::with_capacity(instructions.len()),
+            applied: 0,
+            totals: vec![(0, 0)],
+            tiles: vec![Vec::new(); columns * rows],
+            columns,
+            lights,
+            snapshots: Vec::new(),
+            interval: SNAPSHOT_INTERVAL,
+            max_snapshots: memory
+                / (grid.cells() * (mem::size_of::<bool>() + mem::size_of::<i32>())),
+        };
+        for instruction in instructions {
+            history.push(instruction.clone());
+        }
+
+        Ok(history)
+    }
+
+    /// The number of applied instructions, which is the current step.
+    pub fn steps(&self) -> usize {
+        self.applied
+    }
+
+    /// The number of lights that are on after `step`.
+    pub fn lit_after(&self, step: usize) -> Option<u64> {
+        self.totals
+            .get(step)
+            .filter(|_| step <= self.applied)
+            .map(|t| t.0)
+    }
+
+    /// The total brightness after `step`.
+    pub fn brightness_after(&self, step: usize) -> Option<i64> {
+        self.totals
+            .get(step)
+            .filter(|_| step <= self.applied)
+            .map(|t| t.1)
+    }
+
+    /// The instructions up to `step` that touch `coordinate`, with their
+    /// numbers. A step past the current one counts as the current one.
+    fn touching(
+        &self,
+        coordinate: Coordinate,
+        step: usize,
+    ) -> impl Iterator<Item = (usize, &Instruction)> {
+        let step = step.min(self.applied);
+        let tile: &[usize] = if self.grid.contains(coordinate) {
+            let (column, row) = (coordinate.0 / TILE, coordinate.1 / TILE);
+            &self.tiles[row as usize * self.columns + column as usize]
+        } else {
+            &[]
+        };
+
+        tile.iter()
+            .take_while(move |idx| **idx < step)
+            .map(move |idx| (idx + 1, &self.instructions[*idx]))
+            .filter(move |(_, instruction)| instruction.range.includes(coordinate))
+    }
+
+    /// The brightness of the light at `coordinate` after `step`.
+    pub fn brightness_at(&self, coordinate: Coordinate, step: usize) -> i32 {
+        self.touching(coordinate, step)
+            .fold(0, |brightness, (_, instruction)| {
+                Brightness.apply(instruction.action, brightness)
+            })
+    }
+
+    /// The number of the last instruction up to `step` that switched the
+    /// light at `coordinate` on or off, if any did. Only part one's switching
+    /// counts: an instruction that changes the brightness but leaves the
+    /// light on or off is not a change.
+    pub fn last_change(&self, coordinate: Coordinate, step: usize) -> Option<usize> {
+        self.touching(coordinate, step)
+            .fold((false, None), |(on, last), (number, instruction)| {
+                let switched = Switches.apply(instruction.action, on);
+                (switched, if switched != on { Some(number) } else { last })
+            })
+            .1
+    }
+
+    /// Applies `instruction` after the current step, dropping the undone
+    /// instructions.
+    pub fn push(&mut self, instruction: Instruction) {
+        self.truncate();
+
+        let idx = self.instructions.len();
+        let Range(start, end) = instruction.range;
+        for row in start.1 / TILE..=end.1 / TILE {
+            for column in start.0 / TILE..=end.0 / TILE {
+                self.tiles[row as usize * self.columns + column as usize].push(idx);
+            }
+        }
+
+        let (lit, brightness) = self.lights.apply(&self.grid, &instruction);
+        let (total_lit, total_brightness) = self.totals[idx];
+        self.totals.push((
+            (total_lit as i64 + lit) as u64,
+            total_brightness + brightness,
+        ));
+        self.instructions.push(instruction);
+        self.applied += 1;
+
+        if self.max_snapshots > 0 && self.applied.is_multiple_of(self.interval) {
+            if self.snapshots.len() == self.max_snapshots {
+                self.thin();
+            }
+            if self.applied.is_multiple_of(self.interval) {
+                self.snapshots.push(self.lights.clone());
+            }
+        }
+    }
+
+    /// Drops every other snapshot, doubling the interval between them.
+    fn thin(&mut self) {
+        let mut snapshots = mem::take(&mut self.snapshots).into_iter();
+        self.snapshots = snapshots.by_ref().skip(1).step_by(2).collect();
+        self.interval *= 2;
+    }
+
+    /// Takes back the last applied instruction.
+    pub fn undo(&mut self) -> Option<&Instruction> {
+        self.applied = self.applied.checked_sub(1)?;
+        Some(&self.instructions[self.applied])
+    }
+
+    /// Applies the last undone instruction again.
+    pub fn redo(&mut self) -> Option<&Instruction> {
+        let instruction = self.instructions.get(self.applied)?;
+        self.applied += 1;
+        Some(instruction)
+    }
+
+    /// Forgets the undone instructions, and rewinds the lights to the
+    /// current step from the last snapshot before it.
+    fn truncate(&mut self) {
+        if self.applied == self.instructions.len() {
+            return;
+        }
+
+        self.instructions.truncate(self.applied);
+        self.totals.truncate(self.applied + 1);
+        let applied = self.applied;
+        for tile in &mut self.tiles {
+            tile.truncate(tile.partition_point(|idx| *idx < applied));
+        }
+
+        self.snapshots.truncate(self.applied / self.interval);
+        self.lights = match self.snapshots.last() {
+            Some(lights) => lights.clone(),
+            None => Lights::dark(&self.grid),
+        };
+        let replayed = self.snapshots.len() * self.interval;
+        for instruction in &self.instructions[replayed..] {
+            self.lights.apply(&self.grid, instruction);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calc_brightness, count_lit_grid, total_brightness_grid, Action};
+
+    fn grid() -> LightGrid {
+        LightGrid {
+            width: 150,
+            height: 90,
+        }
+    }
+
+    /// A fixed list of pseudo-random instructions that fit `grid()`.
+    fn instructions(count: usize, mut seed: u64) -> Vec<Instruction> {
+        let mut next = |below: u32| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % below as u64) as u32
+        };
+
+        (0..count)
+            .map(|_| {
+                let action = [Action::TurnOn, Action::TurnOff, Action::Toggle][next(3) as usize];
+                let range = Range(
+                    Coordinate(next(150), next(90)),
+                    Coordinate(next(150), next(90)),
+                );
+                Instruction {
+                    action,
+                    range: grid().normalize(range).unwrap(),
+                }
+            })
+            .collect()
+    }
+
+    fn last_change(coordinate: Coordinate, instructions: &[Instruction]) -> Option<usize> {
+        let mut on = false;
+        let mut last = None;
+        for (idx, instruction) in instructions.iter().enumerate() {
+            let switched = instruction.execute(coordinate, on);
+            if switched != on {
+                last = Some(idx + 1);
+            }
+            on = switched;
+        }
+        last
+    }
+
+    fn assert_matches_replay(history: &History, instructions: &[Instruction]) {
+        assert_eq!(history.steps(), instructions.len());
+        for step in (0..=instructions.len()).step_by(7) {
+            let prefix = &instructions[..step];
+            assert_eq!(
+                history.lit_after(step),
+                Some(count_lit_grid(&grid(), prefix))
+            );
+            assert_eq!(
+                history.brightness_after(step),
+                Some(total_brightness_grid(&grid(), prefix) as i64)
+            );
+            for coordinate in &[Coordinate(0, 0), Coordinate(70, 45), Coordinate(149, 89)] {
+                assert_eq!(
+                    history.brightness_at(*coordinate, step),
+                    calc_brightness(*coordinate, prefix)
+                );
+                assert_eq!(
+                    history.last_change(*coordinate, step),
+                    last_change(*coordinate, prefix)
+                );
+            }
+        }
+        assert_eq!(history.lit_after(instructions.len() + 1), None);
+    }
+
+    #[test]
+    fn queries() {
+        let instructions = instructions(150, 0x2015_0006);
+        let history = History::new(&grid(), &instructions).unwrap();
+
+        assert_matches_replay(&history, &instructions);
+        assert_eq!(history.brightness_at(Coordinate(150, 0), 150), 0);
+        assert!(History::new(
+            &LightGrid {
+                width: 1 << 20,
+                height: 1 << 20
+            },
+            &[]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut instructions = instructions(150, 0x2015_0606);
+        let mut history = History::new(&grid(), &instructions).unwrap();
+
+        for _ in 0..80 {
+            history.undo();
+        }
+        assert_eq!(history.steps(), 70);
+        assert_eq!(history.lit_after(71), None);
+        assert_eq!(history.redo(), Some(&instructions[70]));
+        assert_eq!(
+            history.lit_after(71),
+            Some(count_lit_grid(&grid(), &instructions[..71]))
+        );
+
+        // A new instruction replaces the undone ones.
+        let extra = self::instructions(30, 7);
+        for instruction in &extra {
+            history.push(instruction.clone());
+        }
+        instructions.truncate(71);
+        instructions.extend(extra);
+        assert_eq!(history.redo(), None);
+        assert_matches_replay(&history, &instructions);
+
+        while history.undo().is_some() {}
+        assert_eq!(history.steps(), 0);
+        history.push(instructions[0].clone());
+        assert_matches_replay(&history, &instructions[..1]);
+    }
+
+    #[test]
+    fn snapshot_memory() {
+        let instructions = instructions(600, 0x2015_0660);
+        let snapshot = grid().cells() * 5;
+        let mut history = History::with_memory(&grid(), &instructions, 3 * snapshot).unwrap();
+
+        assert_eq!(history.snapshots.len(), 2);
+        assert_eq!(history.interval, 4 * SNAPSHOT_INTERVAL);
+        for _ in 0..300 {
+            history.undo();
+        }
+        history.push(instructions[300].clone());
+        assert_matches_replay(&history, &instructions[..301]);
+
+        let history = History::with_memory(&grid(), &instructions, snapshot - 1).unwrap();
+        assert!(history.snapshots.is_empty());
+        assert_matches_replay(&history, &instructions);
+    }
+}
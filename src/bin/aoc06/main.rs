@@ -0,0 +1,546 @@
+use std::convert::TryFrom;
+use std::{fmt, path::Path, str::FromStr};
+
+use aoc_2015::cli::Args;
+use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::variants::Implementations;
+use aoc_2015::Answer;
+use tracing::trace;
+
+mod grid;
+mod history;
+mod model;
+mod render;
+mod sweep;
+
+use grid::LightGrid;
+use model::{Brightness, LightModel, Switches};
+use sweep::{sweep, Dimmer, Switch};
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum Action {
+    TurnOn,
+    TurnOff,
+    Toggle,
+}
+#[derive(PartialEq, Debug, Copy, Clone)]
+struct Coordinate(u32, u32);
+
+#[derive(PartialEq, Debug, Clone)]
+struct Range(Coordinate, Coordinate);
+
+#[derive(PartialEq, Debug, Clone)]
+struct Instruction {
+    range: Range,
+    action: Action,
+}
+
+#[derive(PartialEq, Debug)]
+struct ParseError;
+
+/// Why a line of the input is not a usable instruction. Lines are 1-based.
+#[derive(PartialEq, Debug)]
+enum InstructionError {
+    Invalid(usize),
+    OutOfBounds {
+        line: usize,
+        corner: Coordinate,
+        grid: LightGrid,
+    },
+}
+
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstructionError::Invalid(line) => write!(f, "line {}: invalid instruction", line),
+            InstructionError::OutOfBounds { line, corner, grid } => write!(
+                f,
+                "line {}: corner {},{} is outside the {} grid",
+                line, corner.0, corner.1, grid
+            ),
+        }
+    }
+}
+
+impl Into<(u32, u32)> for Coordinate {
+    fn into(self) -> (u32, u32) {
+        (self.0, self.1)
+    }
+}
+
+impl Range {
+    fn includes(self: &Self, coordinate: Coordinate) -> bool {
+        let (x, y) = coordinate.into();
+        let (x_start, y_start) = self.0.into();
+        let (x_end, y_end) = self.1.into();
+
+        x_start <= x && x <= x_end && y_start <= y && y <= y_end
+    }
+}
+
+impl Instruction {
+    fn execute(self: &Self, coordinate: Coordinate, status: bool) -> bool {
+        if self.range.includes(coordinate) {
+            Switches.apply(self.action, status)
+        } else {
+            status
+        }
+    }
+
+    fn update_brightness(self: &Self, coordinate: Coordinate, brightness: i32) -> i32 {
+        if self.range.includes(coordinate) {
+            Brightness.apply(self.action, brightness)
+        } else {
+            brightness
+        }
+    }
+}
+
+impl FromStr for Coordinate {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.splitn(2, ',').collect();
+
+        if parts.len() == 2 {
+            parts[0]
+                .parse::<u32>()
+                .and_then(|a| parts[1].parse::<u32>().and_then(|b| Ok(Coordinate(a, b))))
+                .map_err(|_| ParseError)
+        } else {
+            Err(ParseError)
+        }
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.splitn(2, " through ").collect();
+        if parts.len() == 2 {
+            Coordinate::from_str(parts[0])
+                .and_then(|c1| Coordinate::from_str(parts[1]).map(|c2| Range(c1, c2)))
+                .map_err(|_| ParseError)
+        } else {
+            Err(ParseError)
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "turn on" => Ok(Action::TurnOn),
+            "turn off" => Ok(Action::TurnOff),
+            "toggle" => Ok(Action::Toggle),
+            _ => Err(ParseError),
+        }
+    }
+}
+
+fn calc_status(coord: Coordinate, instructions: &[Instruction]) -> bool {
+    instructions.iter().fold(false, |status, instruction| {
+        instruction.execute(coord, status)
+    })
+}
+
+fn calc_brightness(coord: Coordinate, instructions: &[Instruction]) -> i32 {
+    instructions.iter().fold(0i32, |brightness, instruction| {
+        instruction.update_brightness(coord, brightness)
+    })
+}
+
+fn count_lit_per_cell(grid: &LightGrid, instructions: &[Instruction]) -> u64 {
+    grid.coordinates()
+        .filter(|c| calc_status(*c, instructions))
+        .count() as u64
+}
+
+fn total_brightness_per_cell(grid: &LightGrid, instructions: &[Instruction]) -> i128 {
+    grid.coordinates()
+        .map(|coord| calc_brightness(coord, instructions) as i128)
+        .sum()
+}
+
+/// Applies one instruction to the cells inside its range of a row-major
+/// grid of cells.
+fn apply_instruction<T: Copy>(
+    size: &LightGrid,
+    grid: &mut [T],
+    instruction: &Instruction,
+    update: &impl Fn(&Instruction, Coordinate, T) -> T,
+) {
+    let Range(Coordinate(x_start, y_start), Coordinate(x_end, y_end)) = instruction.range;
+    trace!(action = ?instruction.action, x_start, y_start, x_end, y_end, "applying");
+
+    for y in y_start..=y_end {
+        for x in x_start..=x_end {
+            let cell = &mut grid[size.index(Coordinate(x, y))];
+            *cell = update(instruction, Coordinate(x, y), *cell);
+        }
+    }
+}
+
+/// Applies every instruction to the cells inside its range only, instead of
+/// checking every instruction for every cell.
+fn apply_to_grid<T: Copy>(
+    size: &LightGrid,
+    instructions: &[Instruction],
+    initial: T,
+    update: impl Fn(&Instruction, Coordinate, T) -> T,
+) -> Vec<T> {
+    let mut grid = vec![initial; size.cells()];
+
+    for instruction in instructions {
+        apply_instruction(size, &mut grid, instruction, &update);
+    }
+
+    grid
+}
+
+fn count_lit_grid(grid: &LightGrid, instructions: &[Instruction]) -> u64 {
+    apply_to_grid(grid, instructions, false, Instruction::execute)
+        .into_iter()
+        .filter(|status| *status)
+        .count() as u64
+}
+
+fn total_brightness_grid(grid: &LightGrid, instructions: &[Instruction]) -> i128 {
+    apply_to_grid(grid, instructions, 0, Instruction::update_brightness)
+        .into_iter()
+        .map(i128::from)
+        .sum()
+}
+
+fn count_lit_sweep(grid: &LightGrid, instructions: &[Instruction]) -> u64 {
+    let lit = sweep(instructions, grid, Switch::new, |switch| {
+        switch.is_lit() as i64
+    });
+    u64::try_from(lit).expect("a grid has fewer than 2^64 lights")
+}
+
+fn total_brightness_sweep(grid: &LightGrid, instructions: &[Instruction]) -> i128 {
+    sweep(instructions, grid, Dimmer::new, Dimmer::brightness)
+}
+
+#[derive(Clone, Copy)]
+struct Lights {
+    count_lit: fn(&LightGrid, &[Instruction]) -> u64,
+    total_brightness: fn(&LightGrid, &[Instruction]) -> i128,
+    /// Whether every light is kept in memory, which limits the grid size.
+    stores_cells: bool,
+}
+
+fn implementations() -> Implementations<Lights> {
+    Implementations::new(
+        "sweep",
+        Lights {
+            count_lit: count_lit_sweep,
+            total_brightness: total_brightness_sweep,
+            stores_cells: false,
+        },
+    )
+    .register(
+        "grid",
+        Lights {
+            count_lit: count_lit_grid,
+            total_brightness: total_brightness_grid,
+            stores_cells: true,
+        },
+    )
+    .register(
+        "per-cell",
+        Lights {
+            count_lit: count_lit_per_cell,
+            total_brightness: total_brightness_per_cell,
+            stores_cells: true,
+        },
+    )
+}
+
+/// Parses the puzzle's instructions, which are the commands of the
+/// `Switches` model.
+fn parse_instructions(
+    data: &[String],
+    grid: &LightGrid,
+) -> Result<Vec<Instruction>, InstructionError> {
+    Ok(model::parse_commands(&Switches, data, grid)?
+        .into_iter()
+        .map(|command| Instruction {
+            range: command.range,
+            action: command.verb,
+        })
+        .collect())
+}
+
+fn solve_a(instructions: &[Instruction], grid: &LightGrid, lights: Lights) -> Answer {
+    (lights.count_lit)(grid, instructions).into()
+}
+
+fn solve_b(instructions: &[Instruction], grid: &LightGrid, lights: Lights) -> Answer {
+    (lights.total_brightness)(grid, instructions).into()
+}
+
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+    let lights = implementations().select(args.value("impl"))?;
+    let grid = match args.value("grid") {
+        Some(size) => size.parse()?,
+        None => LightGrid::default(),
+    };
+    if lights.stores_cells {
+        let name = args.value("impl").unwrap_or_default();
+        grid.check_cells(&format!("--impl {}, use sweep", name))?;
+    }
+
+    let data = logging::read(read_input);
+    let instructions =
+        logging::parse(|| parse_instructions(&data, &grid)).map_err(|e| e.to_string())?;
+
+    let solution_a = logging::solve("a", || solve_a(&instructions, &grid, lights));
+    let solution_b = logging::solve("b", || solve_b(&instructions, &grid, lights));
+
+    println!("{}", solution_a);
+    println!("{}", solution_b);
+
+    // `--model SPEC` reads the instructions with other semantics.
+    if let Some(spec) = args.value("model") {
+        let solution = logging::solve("model", || model::run(spec, &data, &grid));
+        println!("{}", solution?);
+    }
+
+    // `--image FILE` draws the final grid and `--frames DIR` every
+    // `--every K`th step, either as lights or by `--render brightness`.
+    let mode = match args.value("render") {
+        Some(mode) => mode.parse()?,
+        None => render::Mode::Lights,
+    };
+    if let Some(path) = args.value("image") {
+        render::write_image(Path::new(path), &grid, &instructions, mode)?;
+    }
+    if let Some(directory) = args.value("frames") {
+        let every = match args.value("every") {
+            Some(every) => every
+                .parse()
+                .map_err(|_| format!("Invalid frame interval '{}'", every))?,
+            None => 1,
+        };
+        let count = render::write_frames(Path::new(directory), &grid, &instructions, mode, every)?;
+        eprintln!("Wrote {} frames to {}", count, directory);
+    }
+
+    // `--edit OPS` undoes, redoes or adds instructions, separated by `;`,
+    // after which `--after K` and `--cell X,Y` query the grid at step `K`,
+    // or at the last step.
+    if ["edit", "after", "cell"]
+        .iter()
+        .any(|flag| args.value(flag).is_some())
+    {
+        let mut history = history::History::new(&grid, &instructions)?;
+        for op in args.value("edit").unwrap_or("").split(';') {
+            match op.trim() {
+                "" => {}
+                "undo" => {
+                    history.undo().ok_or("Nothing to undo")?;
+                }
+                "redo" => {
+                    history.redo().ok_or("Nothing to redo")?;
+                }
+                line => {
+                    let mut added = parse_instructions(&[line.to_string()], &grid)
+                        .map_err(|_| format!("Invalid edit '{}'", line))?;
+                    history.push(added.remove(0));
+                }
+            }
+        }
+
+        let step = match args.value("after") {
+            Some(step) => step
+                .parse()
+                .map_err(|_| format!("Invalid step '{}'", step))?,
+            None => history.steps(),
+        };
+        let (lit, brightness) = match (history.lit_after(step), history.brightness_after(step)) {
+            (Some(lit), Some(brightness)) => (lit, brightness),
+            _ => return Err(format!("There are only {} steps", history.steps())),
+        };
+        println!("step {}: {} lit, brightness {}", step, lit, brightness);
+
+        if let Some(cell) = args.value("cell") {
+            let coordinate = cell
+                .parse()
+                .ok()
+                .filter(|coordinate| grid.contains(*coordinate))
+                .ok_or_else(|| format!("Invalid cell '{}', expected x,y inside the grid", cell))?;
+            let brightness = history.brightness_at(coordinate, step);
+            match history.last_change(coordinate, step) {
+                Some(number) => println!(
+                    "{}: brightness {}, last switched by instruction {}",
+                    cell, brightness, number
+                ),
+                None => println!("{}: brightness {}, never switched", cell, brightness),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn parse_coordinate() {
+        assert_eq!("324,123".parse(), Ok(Coordinate(324, 123)));
+    }
+
+    #[test]
+    fn parse_range() {
+        assert_eq!(
+            "995,774 through 997,784".parse(),
+            Ok(Range(Coordinate(995, 774), Coordinate(997, 784)))
+        );
+    }
+
+    #[test]
+    fn parse_instruction() {
+        assert_eq!(
+            parse_instructions(
+                &["turn off 854,56 through 965,591".to_string()],
+                &LightGrid::default()
+            ),
+            Ok(vec![Instruction {
+                action: Action::TurnOff,
+                range: Range(Coordinate(854, 56), Coordinate(965, 591))
+            }])
+        );
+    }
+
+    #[test]
+    fn brightness() {
+        let data = vec![
+            "turn on 0,0 through 0,0".to_string(),
+            "toggle 0,0 through 999,999".to_string(),
+        ];
+        let instructions = parse_instructions(&data, &LightGrid::default()).unwrap();
+        assert_eq!(
+            (0..1000)
+                .flat_map(|y| (0..1000).map(move |x| Coordinate(x, y)))
+                .map(|coord| calc_brightness(coord, &instructions))
+                .sum::<i32>(),
+            2_000_001
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let grid = LightGrid {
+            width: 10,
+            height: 10,
+        };
+        let data: Vec<String> = vec![
+            "turn on 5,5 through 2,3".to_string(),
+            "".to_string(),
+            "toggle 0,0 through 9,10".to_string(),
+        ];
+
+        assert_eq!(
+            parse_instructions(&data[..2], &grid),
+            Ok(vec![Instruction {
+                action: Action::TurnOn,
+                range: Range(Coordinate(2, 3), Coordinate(5, 5))
+            }])
+        );
+        assert_eq!(
+            parse_instructions(&data, &grid).unwrap_err().to_string(),
+            "line 3: corner 9,10 is outside the 10x10 grid"
+        );
+        assert_eq!(
+            parse_instructions(&["turn up 1,1 through 2,2".to_string()], &grid),
+            Err(InstructionError::Invalid(1))
+        );
+    }
+
+    #[test]
+    fn large_grid() {
+        let grid = LightGrid {
+            width: 1_000_000,
+            height: 1_000_000,
+        };
+        let data: Vec<String> = vec![
+            "turn on 0,0 through 999999,999999".to_string(),
+            "toggle 0,0 through 499999,999999".to_string(),
+            "turn off 250000,250000 through 749999,749999".to_string(),
+        ];
+        let instructions = parse_instructions(&data, &grid).unwrap();
+        let lights = implementations().select(None).unwrap();
+
+        assert_eq!(
+            solve_a(&instructions, &grid, lights),
+            Answer::from(500_000_000_000u64 - 125_000_000_000)
+        );
+        assert_eq!(
+            solve_b(&instructions, &grid, lights),
+            Answer::from(2_000_000_000_000u64 - 250_000_000_000)
+        );
+    }
+
+    #[test]
+    fn max_grid() {
+        let grid = LightGrid {
+            width: u32::MAX,
+            height: u32::MAX,
+        };
+        let data: Vec<String> = vec![
+            "toggle 0,0 through 4294967294,4294967294".to_string(),
+            "toggle 0,0 through 4294967294,4294967294".to_string(),
+            "turn on 0,0 through 0,0".to_string(),
+        ];
+        let instructions = parse_instructions(&data, &grid).unwrap();
+        let lights = implementations().select(Some("sweep")).unwrap();
+        let cells = u32::MAX as i128 * u32::MAX as i128;
+
+        assert_eq!(solve_a(&instructions, &grid, lights), Answer::from(1));
+        assert_eq!(
+            solve_b(&instructions, &grid, lights),
+            Answer::from(4 * cells + 1)
+        );
+    }
+
+    fn instruction() -> impl Strategy<Value = Instruction> {
+        (
+            prop_oneof![
+                Just(Action::TurnOn),
+                Just(Action::TurnOff),
+                Just(Action::Toggle)
+            ],
+            0u32..1000,
+            0u32..1000,
+            0u32..1000,
+            0u32..1000,
+        )
+            .prop_map(|(action, x1, y1, x2, y2)| Instruction {
+                action,
+                range: Range(Coordinate(x1, y1), Coordinate(x2, y2)),
+            })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(8))]
+
+        #[test]
+        fn implementations_agree(instructions in vec(instruction(), 0..8)) {
+            let grid = LightGrid::default();
+            implementations().assert_agree(|lights| (lights.count_lit)(&grid, &instructions));
+            implementations().assert_agree(|lights| (lights.total_brightness)(&grid, &instructions));
+        }
+    }
+}
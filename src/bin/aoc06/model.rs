@@ -0,0 +1,450 @@
+use std::cmp::{max, min};
+use std::fmt::Debug;
+use std::iter::Sum;
+use std::str::FromStr;
+
+use aoc_2015::Answer;
+
+use crate::grid::LightGrid;
+use crate::{Action, Coordinate, InstructionError, Range};
+
+/// What a light adds to the answer, which the integral models add up
+/// exactly.
+pub(crate) trait Measure: Copy + Sum {
+    fn answer(self) -> Answer;
+}
+
+impl Measure for u64 {
+    fn answer(self) -> Answer {
+        Answer::from(self)
+    }
+}
+
+impl Measure for i64 {
+    fn answer(self) -> Answer {
+        Answer::from(self)
+    }
+}
+
+/// Whole totals are answered as integers.
+impl Measure for f64 {
+    fn answer(self) -> Answer {
+        if self.fract() == 0.0 && self.abs() < i64::MAX as f64 {
+            Answer::from(self as i64)
+        } else {
+            Answer::from(self.to_string())
+        }
+    }
+}
+
+/// What the instructions mean: which verbs there are and what they do to a
+/// single light.
+pub(crate) trait LightModel {
+    type Verb: Copy + Debug;
+    type State: Copy + Default;
+    type Measure: Measure;
+
+    /// Parses the part of an instruction before its range, like `turn on`.
+    fn parse_verb(&self, verb: &str) -> Option<Self::Verb>;
+    fn apply(&self, verb: Self::Verb, state: Self::State) -> Self::State;
+    /// What a light adds to the answer.
+    fn measure(&self, state: Self::State) -> Self::Measure;
+}
+
+/// An instruction for a particular model.
+#[derive(Debug, PartialEq)]
+pub struct Command<V> {
+    pub range: Range,
+    pub verb: V,
+}
+
+/// Splits a line into its verb and its range, which are the last two
+/// coordinates joined by `through`.
+fn split_verb(line: &str) -> Option<(&str, &str)> {
+    let through = line.rfind(" through ")?;
+    let start = line[..through].rfind(' ').map_or(0, |idx| idx + 1);
+
+    Some((line[..start].trim(), &line[start..]))
+}
+
+/// Parses every non-blank line, with the corners of each range in order and
+/// inside `grid`.
+pub fn parse_commands<M: LightModel>(
+    model: &M,
+    data: &[String],
+    grid: &LightGrid,
+) -> Result<Vec<Command<M::Verb>>, InstructionError> {
+    data.iter()
+        .enumerate()
+        .filter(|(_, s)| !s.trim().is_empty())
+        .map(|(idx, s)| {
+            let invalid = || InstructionError::Invalid(idx + 1);
+            let (verb, range) = split_verb(s.trim()).ok_or_else(invalid)?;
+            let verb = model.parse_verb(verb).ok_or_else(invalid)?;
+            let range = range.parse().map_err(|_| invalid())?;
+            let range = grid
+                .normalize(range)
+                .map_err(|corner| InstructionError::OutOfBounds {
+                    line: idx + 1,
+                    corner,
+                    grid: *grid,
+                })?;
+
+            Ok(Command { range, verb })
+        })
+        .collect()
+}
+
+/// Applies the commands light by light and adds up the measure of every
+/// light.
+pub fn evaluate<M: LightModel>(
+    model: &M,
+    grid: &LightGrid,
+    commands: &[Command<M::Verb>],
+) -> M::Measure {
+    let mut lights = vec![M::State::default(); grid.cells()];
+
+    for Command { range, verb } in commands {
+        let Range(start, end) = range;
+        for y in start.1..=end.1 {
+            let row = grid.index(Coordinate(0, y));
+            for light in &mut lights[row + start.0 as usize..=row + end.0 as usize] {
+                *light = model.apply(*verb, *light);
+            }
+        }
+    }
+
+    lights.into_iter().map(|light| model.measure(light)).sum()
+}
+
+/// Part one: lights are on or off.
+pub struct Switches;
+
+impl LightModel for Switches {
+    type Verb = Action;
+    type State = bool;
+    type Measure = u64;
+
+    fn parse_verb(&self, verb: &str) -> Option<Action> {
+        verb.parse().ok()
+    }
+
+    fn apply(&self, action: Action, status: bool) -> bool {
+        match action {
+            Action::TurnOn => true,
+            Action::TurnOff => false,
+            Action::Toggle => !status,
+        }
+    }
+
+    fn measure(&self, status: bool) -> u64 {
+        status as u64
+    }
+}
+
+/// Part two: turning on and off adds and takes away one level of
+/// brightness, toggling adds two.
+pub struct Brightness;
+
+impl LightModel for Brightness {
+    type Verb = Action;
+    type State = i32;
+    type Measure = i64;
+
+    fn parse_verb(&self, verb: &str) -> Option<Action> {
+        verb.parse().ok()
+    }
+
+    fn apply(&self, action: Action, brightness: i32) -> i32 {
+        match action {
+            Action::TurnOn => brightness.saturating_add(1),
+            Action::TurnOff => max(brightness - 1, 0),
+            Action::Toggle => brightness.saturating_add(2),
+        }
+    }
+
+    fn measure(&self, brightness: i32) -> i64 {
+        brightness as i64
+    }
+}
+
+/// Part two with lights that can't get brighter than `max`.
+pub struct Capped {
+    pub max: i32,
+}
+
+impl LightModel for Capped {
+    type Verb = Action;
+    type State = i32;
+    type Measure = i64;
+
+    fn parse_verb(&self, verb: &str) -> Option<Action> {
+        verb.parse().ok()
+    }
+
+    fn apply(&self, action: Action, brightness: i32) -> i32 {
+        min(Brightness.apply(action, brightness), self.max)
+    }
+
+    fn measure(&self, brightness: i32) -> i64 {
+        brightness as i64
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Level {
+    Step(Action),
+    /// `set N` puts the brightness at exactly `N`.
+    Set(i32),
+}
+
+/// Part two with an extra `set N` verb.
+pub struct Levels;
+
+impl LightModel for Levels {
+    type Verb = Level;
+    type State = i32;
+    type Measure = i64;
+
+    fn parse_verb(&self, verb: &str) -> Option<Level> {
+        match verb.strip_prefix("set ") {
+            Some(level) => level
+                .trim()
+                .parse()
+                .ok()
+                .filter(|level| *level >= 0)
+                .map(Level::Set),
+            None => verb.parse().ok().map(Level::Step),
+        }
+    }
+
+    fn apply(&self, level: Level, brightness: i32) -> i32 {
+        match level {
+            Level::Step(action) => Brightness.apply(action, brightness),
+            Level::Set(level) => level,
+        }
+    }
+
+    fn measure(&self, brightness: i32) -> i64 {
+        brightness as i64
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Dim {
+    Step(Action),
+    /// `scale F` multiplies the brightness by `F`.
+    Scale(f64),
+}
+
+/// Dimmers that multiply: turning on adds one, turning off goes dark,
+/// toggling adds two and `scale F` multiplies.
+pub struct Multiplicative;
+
+impl LightModel for Multiplicative {
+    type Verb = Dim;
+    type State = f64;
+    type Measure = f64;
+
+    fn parse_verb(&self, verb: &str) -> Option<Dim> {
+        match verb.strip_prefix("scale ") {
+            Some(factor) => factor
+                .trim()
+                .parse()
+                .ok()
+                .filter(|factor: &f64| factor.is_finite() && *factor >= 0.0)
+                .map(Dim::Scale),
+            None => verb.parse().ok().map(Dim::Step),
+        }
+    }
+
+    fn apply(&self, dim: Dim, brightness: f64) -> f64 {
+        match dim {
+            Dim::Step(Action::TurnOn) => brightness + 1.0,
+            Dim::Step(Action::TurnOff) => 0.0,
+            Dim::Step(Action::Toggle) => brightness + 2.0,
+            Dim::Scale(factor) => brightness * factor,
+        }
+    }
+
+    fn measure(&self, brightness: f64) -> f64 {
+        brightness
+    }
+}
+
+/// Which of the red, green and blue channels a verb changes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Channels([bool; 3]);
+
+impl FromStr for Channels {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "red" => Ok(Channels([true, false, false])),
+            "green" => Ok(Channels([false, true, false])),
+            "blue" => Ok(Channels([false, false, true])),
+            "white" => Ok(Channels([true; 3])),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Lights with a red, green and blue channel of up to 255 levels each. The
+/// verbs name a colour, like `turn on red` or `toggle white`, and change
+/// those channels the way part two changes brightness.
+pub struct Rgb;
+
+impl LightModel for Rgb {
+    type Verb = (Action, Channels);
+    type State = [u8; 3];
+    type Measure = u64;
+
+    fn parse_verb(&self, verb: &str) -> Option<(Action, Channels)> {
+        let (action, colour) = verb.rsplit_once(' ')?;
+        Some((action.parse().ok()?, colour.parse().ok()?))
+    }
+
+    fn apply(&self, (action, Channels(channels)): (Action, Channels), mut rgb: [u8; 3]) -> [u8; 3] {
+        for (level, selected) in rgb.iter_mut().zip(channels.iter()) {
+            if *selected {
+                *level = min(Brightness.apply(action, *level as i32), 255) as u8;
+            }
+        }
+        rgb
+    }
+
+    fn measure(&self, rgb: [u8; 3]) -> u64 {
+        rgb.iter().map(|level| *level as u64).sum()
+    }
+}
+
+/// Parses and evaluates the instructions with the model named by `spec`:
+/// `switches`, `brightness`, `capped:MAX`, `levels`, `multiplicative` or
+/// `rgb`. Whole totals are answered as integers.
+pub fn run(spec: &str, data: &[String], grid: &LightGrid) -> Result<Answer, String> {
+    fn run_model<M: LightModel>(
+        model: M,
+        data: &[String],
+        grid: &LightGrid,
+    ) -> Result<Answer, String> {
+        grid.check_cells("a model")?;
+        let commands = parse_commands(&model, data, grid).map_err(|e| e.to_string())?;
+        Ok(evaluate(&model, grid, &commands).answer())
+    }
+
+    match spec.split_once(':') {
+        Some(("capped", max)) => {
+            let max = max
+                .parse()
+                .ok()
+                .filter(|max| *max >= 0)
+                .ok_or_else(|| format!("Invalid brightness cap '{}'", max))?;
+            run_model(Capped { max }, data, grid)
+        }
+        _ => match spec {
+            "switches" => run_model(Switches, data, grid),
+            "brightness" => run_model(Brightness, data, grid),
+            "levels" => run_model(Levels, data, grid),
+            "multiplicative" => run_model(Multiplicative, data, grid),
+            "rgb" => run_model(Rgb, data, grid),
+            _ => Err(format!(
+                "Unknown model '{}', expected switches, brightness, capped:MAX, levels, \
+                 multiplicative or rgb",
+                spec
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn small() -> LightGrid {
+        LightGrid {
+            width: 10,
+            height: 10,
+        }
+    }
+
+    #[test]
+    fn verbs() {
+        assert_eq!(
+            split_verb("turn on red 1,2 through 3,4"),
+            Some(("turn on red", "1,2 through 3,4"))
+        );
+        assert_eq!(
+            split_verb("set 5 0,0 through 9,9"),
+            Some(("set 5", "0,0 through 9,9"))
+        );
+        assert_eq!(split_verb("turn on 1,2"), None);
+
+        assert_eq!(Levels.parse_verb("set 7"), Some(Level::Set(7)));
+        assert_eq!(Levels.parse_verb("set -1"), None);
+        assert_eq!(
+            Multiplicative.parse_verb("scale 0.5"),
+            Some(Dim::Scale(0.5))
+        );
+        assert_eq!(Switches.parse_verb("set 7"), None);
+        assert_eq!(
+            Rgb.parse_verb("toggle blue"),
+            Some((Action::Toggle, Channels([false, false, true])))
+        );
+    }
+
+    #[test]
+    fn models() {
+        let grid = small();
+        let data = lines(&[
+            "turn on 0,0 through 9,9",
+            "toggle 0,0 through 4,9",
+            "turn off 0,0 through 1,1",
+        ]);
+
+        assert_eq!(run("switches", &data, &grid), Ok(Answer::from(50)));
+        assert_eq!(run("brightness", &data, &grid), Ok(Answer::from(196)));
+        assert_eq!(run("capped:2", &data, &grid), Ok(Answer::from(146)));
+
+        let data = lines(&["set 5 0,0 through 9,9", "turn off 0,0 through 9,0"]);
+        assert_eq!(run("levels", &data, &grid), Ok(Answer::from(490)));
+
+        let data = lines(&["toggle 0,0 through 9,9", "scale 0.25 0,0 through 9,4"]);
+        assert_eq!(run("multiplicative", &data, &grid), Ok(Answer::from(125)));
+
+        let data = lines(&[
+            "turn on white 0,0 through 9,9",
+            "toggle red 0,0 through 0,9",
+        ]);
+        assert_eq!(run("rgb", &data, &grid), Ok(Answer::from(320)));
+
+        assert_eq!(
+            run("levels", &lines(&["set 5 0,0 through 9,10"]), &grid),
+            Err("line 1: corner 9,10 is outside the 10x10 grid".to_string())
+        );
+        assert!(run("switches", &lines(&["set 5 0,0 through 9,9"]), &grid).is_err());
+        assert!(run("disco", &data, &grid).is_err());
+        assert_eq!(
+            run("capped:-5", &data, &grid),
+            Err("Invalid brightness cap '-5'".to_string())
+        );
+
+        let data = lines(&["set 2147483647 0,0 through 0,0", "turn on 0,0 through 0,0"]);
+        assert_eq!(run("levels", &data, &grid), Ok(Answer::from(i32::MAX)));
+        assert_eq!(
+            run(
+                "switches",
+                &data,
+                &LightGrid {
+                    width: u32::MAX,
+                    height: u32::MAX
+                }
+            ),
+            Err("The 4294967295x4294967295 grid is too large for a model".to_string())
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::cell::Cell;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::grid::LightGrid;
+use crate::{apply_instruction, apply_to_grid, Instruction};
+
+/// The largest grid that is rendered, one pixel per light.
+const MAX_PIXELS: usize = 1 << 26;
+
+/// Which of the two puzzle readings of the instructions to draw.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Mode {
+    /// Part one's on/off lights, as a black and white PGM.
+    Lights,
+    /// Part two's brightness, as a PPM from black through amber to white.
+    Brightness,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lights" => Ok(Mode::Lights),
+            "brightness" => Ok(Mode::Brightness),
+            _ => Err(format!(
+                "Unknown render mode '{}', expected lights or brightness",
+                s
+            )),
+        }
+    }
+}
+
+impl Mode {
+    fn extension(self) -> &'static str {
+        match self {
+            Mode::Lights => "pgm",
+            Mode::Brightness => "ppm",
+        }
+    }
+}
+
+/// A binary PGM with one pixel per light.
+pub fn lights_to_pgm(grid: &LightGrid, lights: &[bool]) -> Vec<u8> {
+    let mut image = format!("P5\n{} {}\n255\n", grid.width, grid.height).into_bytes();
+    image.extend(lights.iter().map(|lit| if *lit { 255 } else { 0 }));
+    image
+}
+
+/// Maps a brightness onto a black-amber-white scale, relative to the
+/// brightest light.
+fn glow(brightness: i32, max: i32) -> [u8; 3] {
+    if brightness <= 0 {
+        return [0, 0, 0];
+    }
+
+    let t = brightness as f64 / max as f64;
+    let level = |scale: f64, offset: f64| ((t * scale - offset).clamp(0.0, 1.0) * 255.0) as u8;
+
+    [level(2.0, 0.0), level(1.3, 0.0), level(2.0, 1.0)]
+}
+
+/// A binary PPM with one pixel per light, scaled so that `max` is white.
+pub fn brightness_to_ppm(grid: &LightGrid, brightness: &[i32], max: i32) -> Vec<u8> {
+    let mut image = format!("P6\n{} {}\n255\n", grid.width, grid.height).into_bytes();
+    image.extend(brightness.iter().flat_map(|b| glow(*b, max).to_vec()));
+    image
+}
+
+fn check_size(grid: &LightGrid) -> Result<(), String> {
+    if grid.cells() > MAX_PIXELS {
+        Err(format!("The {} grid is too large to render", grid))
+    } else {
+        Ok(())
+    }
+}
+
+/// The highest brightness any light reaches while the instructions are
+/// applied, so that every frame of a replay shares one scale.
+fn peak_brightness(grid: &LightGrid, instructions: &[Instruction]) -> i32 {
+    let peak = Cell::new(0);
+    let mut brightness = vec![0; grid.cells()];
+    for instruction in instructions {
+        apply_instruction(
+            grid,
+            &mut brightness,
+            instruction,
+            &|instruction: &Instruction, coordinate, level| {
+                let level = instruction.update_brightness(coordinate, level);
+                peak.set(peak.get().max(level));
+                level
+            },
+        );
+    }
+
+    peak.get()
+}
+
+/// Replays the instructions and hands the image after every `every`-th
+/// instruction to `frame`, starting with the dark grid and always ending
+/// with the final one. Brightness frames are all scaled to the brightest
+/// light of the whole replay, so that they don't flicker.
+fn replay(
+    grid: &LightGrid,
+    instructions: &[Instruction],
+    mode: Mode,
+    every: usize,
+    mut frame: impl FnMut(usize, Vec<u8>) -> io::Result<()>,
+) -> io::Result<()> {
+    let is_frame = |step: usize| step.is_multiple_of(every) || step == instructions.len();
+
+    match mode {
+        Mode::Lights => {
+            let mut lights = vec![false; grid.cells()];
+            frame(0, lights_to_pgm(grid, &lights))?;
+            for (idx, instruction) in instructions.iter().enumerate() {
+                apply_instruction(grid, &mut lights, instruction, &Instruction::execute);
+                if is_frame(idx + 1) {
+                    frame(idx + 1, lights_to_pgm(grid, &lights))?;
+                }
+            }
+        }
+        Mode::Brightness => {
+            let peak = peak_brightness(grid, instructions);
+            let mut brightness = vec![0; grid.cells()];
+            frame(0, brightness_to_ppm(grid, &brightness, peak))?;
+            for (idx, instruction) in instructions.iter().enumerate() {
+                apply_instruction(
+                    grid,
+                    &mut brightness,
+                    instruction,
+                    &Instruction::update_brightness,
+                );
+                if is_frame(idx + 1) {
+                    frame(idx + 1, brightness_to_ppm(grid, &brightness, peak))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the grid after all instructions to `path`.
+pub fn write_image(
+    path: &Path,
+    grid: &LightGrid,
+    instructions: &[Instruction],
+    mode: Mode,
+) -> Result<(), String> {
+    check_size(grid)?;
+
+    let image = match mode {
+        Mode::Lights => lights_to_pgm(
+            grid,
+            &apply_to_grid(grid, instructions, false, Instruction::execute),
+        ),
+        Mode::Brightness => {
+            let brightness = apply_to_grid(grid, instructions, 0, Instruction::update_brightness);
+            let max = brightness.iter().copied().max().unwrap_or(0);
+            brightness_to_ppm(grid, &brightness, max)
+        }
+    };
+    fs::write(path, image).map_err(|e| format!("Error writing {}: {}", path.display(), e))
+}
+
+/// Writes `frame-N.pgm` (or `.ppm`) into `directory` for the grid after
+/// every `every`-th instruction. The numbers are padded to the same width,
+/// so the frames sort in order, and frame 0 is the dark grid.
+pub fn write_frames(
+    directory: &Path,
+    grid: &LightGrid,
+    instructions: &[Instruction],
+    mode: Mode,
+    every: usize,
+) -> Result<usize, String> {
+    check_size(grid)?;
+    if every == 0 {
+        return Err("Frames must be at least one instruction apart".to_string());
+    }
+
+    let digits = instructions.len().to_string().len();
+    let mut count = 0;
+    fs::create_dir_all(directory)
+        .and_then(|_| {
+            replay(grid, instructions, mode, every, |step, image| {
+                count += 1;
+                let name = format!("frame-{:0digits$}.{}", step, mode.extension());
+                fs::write(directory.join(name), image)
+            })
+        })
+        .map_err(|e| format!("Error writing frames to {}: {}", directory.display(), e))?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, Coordinate, Range};
+
+    fn instructions() -> Vec<Instruction> {
+        vec![
+            Instruction {
+                action: Action::Toggle,
+                range: Range(Coordinate(0, 0), Coordinate(1, 0)),
+            },
+            Instruction {
+                action: Action::TurnOff,
+                range: Range(Coordinate(1, 0), Coordinate(2, 1)),
+            },
+            Instruction {
+                action: Action::TurnOn,
+                range: Range(Coordinate(2, 1), Coordinate(2, 1)),
+            },
+        ]
+    }
+
+    #[test]
+    fn images() {
+        let grid = LightGrid {
+            width: 3,
+            height: 2,
+        };
+
+        assert_eq!(
+            lights_to_pgm(&grid, &[true, false, false, false, false, true]),
+            b"P5\n3 2\n255\n\xff\0\0\0\0\xff".to_vec()
+        );
+
+        let image = brightness_to_ppm(&grid, &[4, 0, 1, 0, 0, 2], 4);
+        assert!(image.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(image.len(), 11 + 6 * 3);
+        assert_eq!(&image[11..14], &[255, 255, 255]);
+        assert_eq!(&image[14..17], &[0, 0, 0]);
+        assert_eq!(&image[17..20], &[127, 82, 0]);
+    }
+
+    #[test]
+    fn frames() {
+        let grid = LightGrid {
+            width: 3,
+            height: 2,
+        };
+        let directory = std::env::temp_dir().join(format!("aoc06-frames-{}", std::process::id()));
+
+        let count = write_frames(&directory, &grid, &instructions(), Mode::Lights, 2).unwrap();
+        let mut names: Vec<_> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+
+        assert_eq!(count, 3);
+        assert_eq!(names, vec!["frame-0.pgm", "frame-2.pgm", "frame-3.pgm"]);
+        assert_eq!(
+            fs::read(directory.join("frame-3.pgm")).unwrap(),
+            lights_to_pgm(&grid, &[true, false, false, false, false, true])
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn frames_share_scale() {
+        let grid = LightGrid {
+            width: 1,
+            height: 1,
+        };
+        let light = Range(Coordinate(0, 0), Coordinate(0, 0));
+        let instructions = vec![
+            Instruction {
+                action: Action::Toggle,
+                range: light.clone(),
+            },
+            Instruction {
+                action: Action::TurnOff,
+                range: light,
+            },
+        ];
+        let mut frames = Vec::new();
+
+        replay(&grid, &instructions, Mode::Brightness, 1, |_, image| {
+            frames.push(image[11..].to_vec());
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            frames,
+            vec![vec![0, 0, 0], vec![255, 255, 255], vec![255, 165, 0]]
+        );
+    }
+}
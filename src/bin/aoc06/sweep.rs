@@ -0,0 +1,256 @@
+use std::cmp::max;
+
+use tracing::trace;
+
+use crate::{Action, Coordinate, Instruction, LightGrid, Range};
+
+/// The combined effect of a sequence of instructions on a single light.
+pub trait Effect: Copy {
+    fn identity() -> Self;
+    /// The effect of `self` followed by `next`.
+    fn then(self, next: Self) -> Self;
+}
+
+/// Either forces the light to a state or leaves it, and then maybe flips it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Switch {
+    set: Option<bool>,
+    flip: bool,
+}
+
+impl Switch {
+    pub fn new(action: Action) -> Self {
+        match action {
+            Action::TurnOn => Switch {
+                set: Some(true),
+                flip: false,
+            },
+            Action::TurnOff => Switch {
+                set: Some(false),
+                flip: false,
+            },
+            Action::Toggle => Switch {
+                set: None,
+                flip: true,
+            },
+        }
+    }
+
+    pub fn is_lit(self) -> bool {
+        self.set.unwrap_or(false) ^ self.flip
+    }
+}
+
+impl Effect for Switch {
+    fn identity() -> Self {
+        Switch {
+            set: None,
+            flip: false,
+        }
+    }
+
+    fn then(self, next: Self) -> Self {
+        match next.set {
+            Some(_) => next,
+            None => Switch {
+                flip: self.flip ^ next.flip,
+                ..self
+            },
+        }
+    }
+}
+
+/// Maps a brightness `b` to `max(b + add, floor)`. These compose into a
+/// function of the same shape, so any sequence of instructions is two
+/// numbers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dimmer {
+    add: i64,
+    floor: i64,
+}
+
+impl Dimmer {
+    pub fn new(action: Action) -> Self {
+        let add = match action {
+            Action::TurnOn => 1,
+            Action::TurnOff => -1,
+            Action::Toggle => 2,
+        };
+        Dimmer { add, floor: 0 }
+    }
+
+    pub fn brightness(self) -> i64 {
+        max(self.add, self.floor)
+    }
+}
+
+impl Effect for Dimmer {
+    /// Brightness never drops below zero, so a floor of zero changes nothing.
+    fn identity() -> Self {
+        Dimmer { add: 0, floor: 0 }
+    }
+
+    fn then(self, next: Self) -> Self {
+        Dimmer {
+            add: self.add + next.add,
+            floor: max(self.floor + next.add, next.floor),
+        }
+    }
+}
+
+/// A segment tree that only holds pending effects. Updating a range pushes
+/// the effects on its way down first, so every leaf ends up with the effects
+/// on it in the order they were applied.
+struct Segments<E> {
+    effects: Vec<E>,
+    len: usize,
+}
+
+impl<E: Effect> Segments<E> {
+    fn new(len: usize) -> Self {
+        Segments {
+            effects: vec![E::identity(); 4 * len.max(1)],
+            len,
+        }
+    }
+
+    fn push_down(&mut self, node: usize) {
+        let effect = self.effects[node];
+        for child in [2 * node, 2 * node + 1].iter() {
+            self.effects[*child] = self.effects[*child].then(effect);
+        }
+        self.effects[node] = E::identity();
+    }
+
+    fn update(&mut self, from: usize, to: usize, effect: E) {
+        self.update_node(1, 0, self.len, from, to, effect);
+    }
+
+    fn update_node(
+        &mut self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        from: usize,
+        to: usize,
+        effect: E,
+    ) {
+        if to <= lo || hi <= from {
+            return;
+        }
+        if from <= lo && hi <= to {
+            self.effects[node] = self.effects[node].then(effect);
+            return;
+        }
+
+        self.push_down(node);
+        let mid = (lo + hi) / 2;
+        self.update_node(2 * node, lo, mid, from, to, effect);
+        self.update_node(2 * node + 1, mid, hi, from, to, effect);
+    }
+
+    fn leaves(mut self) -> Vec<E> {
+        let mut leaves = Vec::with_capacity(self.len);
+        self.collect(1, 0, self.len, &mut leaves);
+        leaves
+    }
+
+    fn collect(&mut self, node: usize, lo: usize, hi: usize, leaves: &mut Vec<E>) {
+        if hi - lo == 1 {
+            leaves.push(self.effects[node]);
+            return;
+        }
+
+        self.push_down(node);
+        let mid = (lo + hi) / 2;
+        self.collect(2 * node, lo, mid, leaves);
+        self.collect(2 * node + 1, mid, hi, leaves);
+    }
+}
+
+/// Coordinate compression: the corners of the ranges cut the grid into
+/// blocks of lights that every instruction treats the same. Each column of
+/// blocks is swept with a segment tree over its rows, and `value` of the
+/// resulting effect is counted once per light in the block.
+///
+/// This takes time in the number of instructions only, not in the number of
+/// lights, and adds up in `i128` so that even a grid of `u32::MAX` by
+/// `u32::MAX` lights can't overflow. The ranges must lie within `grid`.
+pub fn sweep<E: Effect>(
+    instructions: &[Instruction],
+    grid: &LightGrid,
+    effect: impl Fn(Action) -> E,
+    value: impl Fn(E) -> i64,
+) -> i128 {
+    let ranges: Vec<_> = instructions
+        .iter()
+        .filter_map(|instruction| {
+            let Range(Coordinate(x_start, y_start), Coordinate(x_end, y_end)) = instruction.range;
+
+            if x_start <= x_end && y_start <= y_end {
+                Some(((x_start, y_start, x_end + 1, y_end + 1), instruction.action))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let boundaries = |corners: fn(&(u32, u32, u32, u32)) -> [u32; 2], size| {
+        let mut boundaries: Vec<u32> = ranges.iter().flat_map(|(r, _)| corners(r)).collect();
+        boundaries.extend_from_slice(&[0, size]);
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        boundaries
+    };
+    let xs = boundaries(|(x_start, _, x_end, _)| [*x_start, *x_end], grid.width);
+    let ys = boundaries(|(_, y_start, _, y_end)| [*y_start, *y_end], grid.height);
+    let row = |y| ys.binary_search(&y).unwrap();
+    trace!(columns = xs.len() - 1, rows = ys.len() - 1, "compressed");
+
+    xs.windows(2)
+        .map(|column| {
+            let mut segments = Segments::new(ys.len() - 1);
+            for ((x_start, y_start, x_end, y_end), action) in &ranges {
+                if *x_start <= column[0] && column[1] <= *x_end {
+                    segments.update(row(*y_start), row(*y_end), effect(*action));
+                }
+            }
+
+            let width = (column[1] - column[0]) as i128;
+            segments
+                .leaves()
+                .into_iter()
+                .zip(ys.windows(2))
+                .map(|(effect, rows)| value(effect) as i128 * width * (rows[1] - rows[0]) as i128)
+                .sum::<i128>()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects() {
+        let effects = |actions: &[Action]| {
+            actions
+                .iter()
+                .fold((Switch::identity(), Dimmer::identity()), |(s, d), a| {
+                    (s.then(Switch::new(*a)), d.then(Dimmer::new(*a)))
+                })
+        };
+
+        let (switch, dimmer) = effects(&[Action::TurnOff, Action::TurnOff, Action::Toggle]);
+        assert!(switch.is_lit());
+        assert_eq!(dimmer.brightness(), 2);
+
+        let (switch, dimmer) = effects(&[Action::Toggle, Action::TurnOff, Action::TurnOn]);
+        assert!(switch.is_lit());
+        assert_eq!(dimmer.brightness(), 2);
+
+        let (switch, dimmer) = effects(&[Action::TurnOn, Action::Toggle, Action::TurnOff]);
+        assert!(!switch.is_lit());
+        assert_eq!(dimmer.brightness(), 2);
+    }
+}
@@ -1,4 +1,7 @@
+use aoc_2015::cli::Args;
 use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::Answer;
 use std::collections::HashMap;
 use std::str::FromStr;
 use Operation::*;
@@ -128,30 +131,35 @@ fn evaluate(circuit: &Circuit, port: &Port, cache: &mut HashMap<String, u16>) ->
     result
 }
 
-fn solve_a(data: &Vec<String>) -> u16 {
+fn solve_a(data: &Vec<String>) -> Answer {
     let circuit = get_circuit(data);
     let mut cache = HashMap::new();
-    evaluate(&circuit, &Port::from_label("a"), &mut cache)
+    evaluate(&circuit, &Port::from_label("a"), &mut cache).into()
 }
 
-fn solve_b(data: &Vec<String>) -> u16 {
+fn solve_b(data: &Vec<String>) -> Answer {
     let circuit = get_circuit(data);
     let mut cache = HashMap::new();
     let new_b = evaluate(&circuit, &Port::from_label("a"), &mut cache);
 
     let mut cache = HashMap::new();
     cache.insert("b".to_string(), new_b);
-    evaluate(&circuit, &Port::from_label("a"), &mut cache)
+    evaluate(&circuit, &Port::from_label("a"), &mut cache).into()
 }
 
-fn main() {
-    let data = read_input();
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
 
-    let solution_a = solve_a(&data);
-    let solution_b = solve_b(&data);
+    let data = logging::read(read_input);
+
+    let solution_a = logging::solve("a", || solve_a(&data));
+    let solution_b = logging::solve("b", || solve_b(&data));
 
     println!("{}", solution_a);
     println!("{}", solution_b);
+
+    Ok(())
 }
 
 #[cfg(test)]
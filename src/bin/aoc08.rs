@@ -1,4 +1,7 @@
+use aoc_2015::cli::Args;
 use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::Answer;
 
 enum State {
     Init,
@@ -49,26 +52,33 @@ fn encode_string(s: &String) -> String {
     format!("\"{}\"", encoded)
 }
 
-fn solve_a(data: &Vec<String>) -> u32 {
+fn solve_a(data: &Vec<String>) -> Answer {
     data.into_iter()
         .map(|s| s.len() as u32 - calculate_length(s))
         .sum::<u32>()
+        .into()
 }
 
-fn solve_b(data: &Vec<String>) -> u32 {
+fn solve_b(data: &Vec<String>) -> Answer {
     data.into_iter()
         .map(|s| (encode_string(s).len() - s.len()) as u32)
-        .sum()
+        .sum::<u32>()
+        .into()
 }
 
-fn main() {
-    let data = read_input();
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+
+    let data = logging::read(read_input);
 
-    let solution_a = solve_a(&data);
-    let solution_b = solve_b(&data);
+    let solution_a = logging::solve("a", || solve_a(&data));
+    let solution_b = logging::solve("b", || solve_b(&data));
 
     println!("{}", solution_a);
     println!("{}", solution_b);
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,11 +1,16 @@
 use itertools::Itertools;
+use std::cmp::{max, min};
 use std::collections::HashMap;
 
+use aoc_2015::cli::Args;
 use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::variants::Implementations;
+use aoc_2015::Answer;
 
 type Lookup = HashMap<String, HashMap<String, usize>>;
 
-fn get_distances(data: &Vec<String>) -> Lookup {
+fn get_distances(data: &[String]) -> Lookup {
     let mut distances = HashMap::new();
 
     for line in data {
@@ -49,21 +54,115 @@ fn iter_distances<'a>(lookup: &'a Lookup) -> impl 'a + Iterator<Item = usize> {
     })
 }
 
-fn solve_a(lookup: &Lookup) -> usize {
-    iter_distances(lookup).min().unwrap()
+fn permutation_extremes(lookup: &Lookup) -> (usize, usize) {
+    iter_distances(lookup).minmax().into_option().unwrap()
 }
 
-fn solve_b(lookup: &Lookup) -> usize {
-    iter_distances(lookup).max().unwrap()
+/// Held-Karp style dynamic programming over (visited set, last city), which
+/// needs O(2^n * n^2) steps instead of the O(n!) of trying every permutation.
+fn held_karp_extremes(lookup: &Lookup) -> (usize, usize) {
+    let cities: Vec<&String> = lookup.keys().collect();
+    let n = cities.len();
+    let distance = |a: usize, b: usize| lookup[cities[a]][cities[b]];
+
+    let mut shortest = vec![vec![usize::MAX; n]; 1 << n];
+    let mut longest = vec![vec![0; n]; 1 << n];
+    for city in 0..n {
+        shortest[1 << city][city] = 0;
+    }
+
+    for visited in 1..(1 << n) {
+        for last in (0..n).filter(|last| visited & (1 << last) != 0) {
+            if shortest[visited][last] == usize::MAX {
+                continue;
+            }
+
+            for next in (0..n).filter(|next| visited & (1 << next) == 0) {
+                let extended = visited | (1 << next);
+                let step = distance(last, next);
+
+                shortest[extended][next] =
+                    min(shortest[extended][next], shortest[visited][last] + step);
+                longest[extended][next] =
+                    max(longest[extended][next], longest[visited][last] + step);
+            }
+        }
+    }
+
+    let all = (1 << n) - 1;
+    (
+        shortest[all].iter().copied().min().unwrap(),
+        longest[all].iter().copied().max().unwrap(),
+    )
+}
+
+type RouteExtremes = fn(&Lookup) -> (usize, usize);
+
+fn implementations() -> Implementations<RouteExtremes> {
+    Implementations::new("held-karp", held_karp_extremes as RouteExtremes)
+        .register("permutations", permutation_extremes)
+}
+
+fn solve_a(lookup: &Lookup, extremes: RouteExtremes) -> Answer {
+    extremes(lookup).0.into()
+}
+
+fn solve_b(lookup: &Lookup, extremes: RouteExtremes) -> Answer {
+    extremes(lookup).1.into()
 }
 
-fn main() {
-    let data = read_input();
-    let distances = get_distances(&data);
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+    let extremes = implementations().select(args.value("impl"))?;
 
-    let solution_a = solve_a(&distances);
-    let solution_b = solve_b(&distances);
+    let data = logging::read(read_input);
+    let distances = logging::parse(|| get_distances(&data));
+
+    let solution_a = logging::solve("a", || solve_a(&distances, extremes));
+    let solution_b = logging::solve("b", || solve_b(&distances, extremes));
 
     println!("{}", solution_a);
     println!("{}", solution_b);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn example() -> Lookup {
+        get_distances(&[
+            "London to Dublin = 464".to_string(),
+            "London to Belfast = 518".to_string(),
+            "Dublin to Belfast = 141".to_string(),
+        ])
+    }
+
+    #[test]
+    fn extremes() {
+        assert_eq!(permutation_extremes(&example()), (605, 982));
+        assert_eq!(held_karp_extremes(&example()), (605, 982));
+    }
+
+    fn lookup(n: usize) -> impl Strategy<Value = Lookup> {
+        vec(1usize..1000, n * (n - 1) / 2).prop_map(move |distances| {
+            let mut distances = distances.into_iter();
+            let lines: Vec<String> = (0..n)
+                .flat_map(|a| ((a + 1)..n).map(move |b| (a, b)))
+                .map(|(a, b)| format!("C{} to C{} = {}", a, b, distances.next().unwrap()))
+                .collect();
+            get_distances(&lines)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn implementations_agree(lookup in (2usize..7).prop_flat_map(lookup)) {
+            implementations().assert_agree(|f| f(&lookup));
+        }
+    }
 }
@@ -1,4 +1,9 @@
+use aoc_2015::cli::Args;
 use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::variants::Implementations;
+use aoc_2015::Answer;
+use tracing::trace;
 
 struct RepeatCount<I>
 where
@@ -62,10 +67,11 @@ trait RepeatCountTrait: Iterator {
 
 impl<T: ?Sized> RepeatCountTrait for T where T: Iterator {}
 
-fn look_and_say(start: &String, n: usize) -> String {
-    let mut result = start.clone();
+fn look_and_say(start: &str, n: usize) -> String {
+    let mut result = start.to_string();
 
-    for _ in 0..n {
+    for round in 0..n {
+        trace!(round, length = result.len(), "look and say");
         result = result
             .chars()
             .repeat_count()
@@ -76,23 +82,75 @@ fn look_and_say(start: &String, n: usize) -> String {
     result
 }
 
-fn solve_a(start: &String) -> usize {
-    look_and_say(start, 40).len()
+fn look_and_say_bytes(start: &str, n: usize) -> String {
+    let mut current = start.as_bytes().to_vec();
+    let mut next = Vec::with_capacity(current.len() * 2);
+
+    for round in 0..n {
+        trace!(round, length = current.len(), "look and say");
+        next.clear();
+
+        let mut idx = 0;
+        while idx < current.len() {
+            let digit = current[idx];
+            let run = current[idx..].iter().take_while(|&&b| b == digit).count();
+            next.extend_from_slice(run.to_string().as_bytes());
+            next.push(digit);
+            idx += run;
+        }
+
+        std::mem::swap(&mut current, &mut next);
+    }
+
+    String::from_utf8(current).unwrap()
+}
+
+type LookAndSay = fn(&str, usize) -> String;
+
+fn implementations() -> Implementations<LookAndSay> {
+    Implementations::new("bytes", look_and_say_bytes as LookAndSay).register("naive", look_and_say)
+}
+
+fn solve_a(start: &str, look_and_say: LookAndSay) -> Answer {
+    look_and_say(start, 40).len().into()
 }
 
-fn solve_b(start: &String) -> usize {
-    look_and_say(start, 50).len()
+fn solve_b(start: &str, look_and_say: LookAndSay) -> Answer {
+    look_and_say(start, 50).len().into()
 }
 
-fn main() -> Result<(), &'static str> {
-    let data = read_input();
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+    let look_and_say = implementations().select(args.value("impl"))?;
+
+    let data = logging::read(read_input);
     let s = data.first().ok_or("Error reading input")?;
 
-    let solution_a = solve_a(s);
-    let solution_b = solve_b(s);
+    let solution_a = logging::solve("a", || solve_a(s, look_and_say));
+    let solution_b = logging::solve("b", || solve_b(s, look_and_say));
 
     println!("{}", solution_a);
     println!("{}", solution_b);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn example() {
+        assert_eq!(look_and_say("1", 5), "312211");
+        assert_eq!(look_and_say_bytes("1", 5), "312211");
+    }
+
+    proptest! {
+        #[test]
+        fn implementations_agree(start in "[1-9]{1,8}", n in 0usize..15) {
+            implementations().assert_agree(|f| f(&start, n));
+        }
+    }
+}
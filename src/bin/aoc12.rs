@@ -1,53 +1,109 @@
+use aoc_2015::cli::Args;
 use aoc_2015::io::read_input;
+use aoc_2015::logging;
+use aoc_2015::Answer;
 use json::ast::Json;
 use json::decoder::Decoder;
 
-fn sum(json: &Json) -> f64 {
+/// The largest magnitude below which every integer is exact in an `f64`.
+const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+
+fn integer(n: f64) -> Result<i64, String> {
+    if n.fract() == 0.0 && n.abs() <= MAX_EXACT {
+        Ok(n as i64)
+    } else {
+        Err(format!(
+            "The document contains {}, which is not an exact integer",
+            n
+        ))
+    }
+}
+
+/// Adds up the sums of the values in an array or object.
+fn total(mut sums: impl Iterator<Item = Result<i128, String>>) -> Result<i128, String> {
+    sums.try_fold(0i128, |total, sum| {
+        total
+            .checked_add(sum?)
+            .ok_or_else(|| "The sum of the document overflows".to_string())
+    })
+}
+
+fn sum(json: &Json) -> Result<i128, String> {
     match json {
-        Json::Number(n) => *n,
-        Json::Array(arr) => arr.into_iter().map(sum).sum(),
-        Json::Object(obj) => obj.values().into_iter().map(sum).sum(),
-        _ => 0f64,
+        Json::Number(n) => integer(*n).map(i128::from),
+        Json::Array(arr) => total(arr.into_iter().map(sum)),
+        Json::Object(obj) => total(obj.values().into_iter().map(sum)),
+        _ => Ok(0),
     }
 }
 
-fn solve_a(s: &String) -> f64 {
+fn solve_a(s: &String) -> Result<Answer, String> {
     let mut decoder = Decoder::default(s.chars());
     let json = decoder.decode().unwrap();
-    sum(&json)
+    sum(&json).map(Answer::from)
 }
 
-fn sum_ignore_red(json: &Json) -> f64 {
+fn sum_ignore_red(json: &Json) -> Result<i128, String> {
     match json {
-        Json::Number(n) => *n,
-        Json::Array(arr) => arr.into_iter().map(sum_ignore_red).sum(),
+        Json::Number(n) => integer(*n).map(i128::from),
+        Json::Array(arr) => total(arr.into_iter().map(sum_ignore_red)),
         Json::Object(obj) => {
             if obj.values().into_iter().any(|v| match v {
                 Json::String(s) => s == "red",
                 _ => false,
             }) {
-                0f64
+                Ok(0)
             } else {
-                obj.values().into_iter().map(sum_ignore_red).sum()
+                total(obj.values().into_iter().map(sum_ignore_red))
             }
         }
-        _ => 0f64,
+        _ => Ok(0),
     }
 }
 
-fn solve_b(s: &String) -> f64 {
+fn solve_b(s: &String) -> Result<Answer, String> {
     let mut decoder = Decoder::default(s.chars());
     let json = decoder.decode().unwrap();
-    sum_ignore_red(&json)
+    sum_ignore_red(&json).map(Answer::from)
 }
 
-fn main() {
-    let data = read_input();
-    let json = data.first().unwrap();
+fn main() -> Result<(), String> {
+    let args = Args::from_env();
+    let _guard = logging::init(&args)?;
+
+    let data = logging::read(read_input);
+    let json = data.first().ok_or("Error reading input")?;
 
-    let solution_a = solve_a(json);
-    let solution_b = solve_b(json);
+    let solution_a = logging::solve("a", || solve_a(json));
+    let solution_b = logging::solve("b", || solve_b(json));
 
-    println!("{}", solution_a);
-    println!("{}", solution_b);
+    println!("{}", solution_a?);
+    println!("{}", solution_b?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            solve_a(&r#"[1,{"c":"red","b":2},3]"#.to_string()),
+            Ok(Answer::from(6))
+        );
+        assert_eq!(
+            solve_b(&r#"[1,{"c":"red","b":2},3]"#.to_string()),
+            Ok(Answer::from(4))
+        );
+        assert!(solve_a(&"[1, 2.5]".to_string()).is_err());
+        assert!(solve_a(&"[1e300]".to_string()).is_err());
+
+        let large = format!("[{}]", vec!["9007199254740992"; 1100].join(","));
+        assert_eq!(
+            solve_a(&large),
+            Ok(Answer::from(9_007_199_254_740_992i128 * 1100))
+        );
+    }
 }
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::env;
+
+/// Command line options shared by all solutions.
+///
+/// Options are written as `--name value`, `--name=value` or as a bare
+/// `--name` switch. Since the puzzle input is read from stdin there are no
+/// positional arguments.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    pub fn from_env() -> Self {
+        Self::parse(env::args().skip(1))
+    }
+
+    pub fn parse<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut options = HashMap::new();
+        let mut args = args.into_iter().map(Into::into).peekable();
+
+        while let Some(arg) = args.next() {
+            let name = match arg.strip_prefix("--") {
+                Some(name) => name,
+                None => continue,
+            };
+
+            match name.split_once('=') {
+                Some((name, value)) => {
+                    options.insert(name.to_string(), Some(value.to_string()));
+                }
+                None => {
+                    let value = args.next_if(|next| !next.starts_with("--"));
+                    options.insert(name.to_string(), value);
+                }
+            }
+        }
+
+        Args { options }
+    }
+
+    /// Returns the value given for `--name`, if any.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name).and_then(|value| value.as_deref())
+    }
+
+    /// Returns whether `--name` was given at all, with or without a value.
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let args = Args::parse(vec!["--impl", "naive", "--strict", "--threads=4"]);
+
+        assert_eq!(args.value("impl"), Some("naive"));
+        assert_eq!(args.value("threads"), Some("4"));
+        assert_eq!(args.value("strict"), None);
+        assert!(args.flag("strict"));
+        assert!(!args.flag("log"));
+    }
+}
@@ -1,11 +1,27 @@
+pub mod answer;
+pub mod cli;
+pub mod logging;
+pub mod outcome;
+pub mod variants;
+
+pub use answer::Answer;
+
 pub mod io {
-    use std::io::{stdin, BufRead};
+    use std::io::{stdin, BufRead, Read};
 
     pub fn read_input() -> Vec<String> {
         let stdin = stdin();
         let lines = stdin.lock().lines();
         lines.map(|line| line.unwrap()).collect()
     }
+
+    /// Reads all of stdin without splitting it into lines, for inputs too
+    /// large to keep as a `String` per line.
+    pub fn read_bytes() -> Vec<u8> {
+        let mut input = Vec::new();
+        stdin().lock().read_to_end(&mut input).unwrap();
+        input
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,80 @@
+use std::io::stderr;
+
+use tracing::info_span;
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+
+use crate::cli::Args;
+use crate::outcome::Outcome;
+
+/// Keeps the Chrome trace file open until it is dropped at the end of `main`.
+pub struct Guard {
+    _flush: Option<FlushGuard>,
+}
+
+/// Installs a subscriber according to the command line.
+///
+/// `--log [LEVEL]` prints events up to `LEVEL` (default `info`) to stderr, and
+/// `--chrome-trace FILE` writes the spans as Chrome trace-event JSON that can
+/// be opened in `chrome://tracing` or Perfetto. Hot loops only emit events at
+/// the `trace` level.
+pub fn init(args: &Args) -> Result<Guard, String> {
+    let chrome_trace = args.value("chrome-trace");
+    if !args.flag("log") && chrome_trace.is_none() {
+        return Ok(Guard { _flush: None });
+    }
+
+    let level = match args.value("log") {
+        Some(level) => level
+            .parse::<LevelFilter>()
+            .map_err(|_| format!("Invalid log level '{}'", level))?,
+        None => LevelFilter::INFO,
+    };
+
+    let fmt_layer = if args.flag("log") {
+        Some(tracing_subscriber::fmt::layer().with_writer(stderr))
+    } else {
+        None
+    };
+
+    let (chrome_layer, guard) = match chrome_trace {
+        Some(path) => {
+            let (layer, guard) = ChromeLayerBuilder::new()
+                .file(path)
+                .include_args(true)
+                .build();
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(level)
+        .with(fmt_layer)
+        .with(chrome_layer)
+        .init();
+
+    Ok(Guard { _flush: guard })
+}
+
+/// Reads the puzzle input inside a `read` span, so that waiting for stdin
+/// is not counted as parsing.
+pub fn read<T>(f: impl FnOnce() -> T) -> T {
+    let _span = info_span!("read").entered();
+    f()
+}
+
+/// Runs the parsing step of a solution inside a `parse` span.
+pub fn parse<T>(f: impl FnOnce() -> T) -> T {
+    let _span = info_span!("parse").entered();
+    f()
+}
+
+/// Runs one part of a solution inside a `solve` span and logs its outcome.
+pub fn solve<T: Outcome>(part: &'static str, f: impl FnOnce() -> T) -> T {
+    let _span = info_span!("solve", part).entered();
+    let outcome = f();
+    outcome.log();
+    outcome
+}
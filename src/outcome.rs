@@ -0,0 +1,26 @@
+use std::fmt::Display;
+
+use tracing::{info, warn};
+
+use crate::Answer;
+
+/// The result of solving a part, which is either an answer or an error
+/// explaining why there is none.
+pub trait Outcome {
+    fn log(&self);
+}
+
+impl Outcome for Answer {
+    fn log(&self) {
+        info!(answer = %self, "solved");
+    }
+}
+
+impl<E: Display> Outcome for Result<Answer, E> {
+    fn log(&self) {
+        match self {
+            Ok(answer) => answer.log(),
+            Err(error) => warn!(%error, "failed"),
+        }
+    }
+}
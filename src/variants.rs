@@ -0,0 +1,119 @@
+use std::fmt::Debug;
+
+/// A named set of interchangeable implementations of the same algorithm.
+///
+/// `F` is usually a function pointer (or a struct of function pointers) so
+/// that days can keep a simple reference implementation next to faster ones.
+/// The first registered implementation is the default.
+#[derive(Debug, Clone)]
+pub struct Implementations<F> {
+    entries: Vec<(&'static str, F)>,
+}
+
+impl<F: Copy> Implementations<F> {
+    pub fn new(name: &'static str, implementation: F) -> Self {
+        Implementations {
+            entries: vec![(name, implementation)],
+        }
+    }
+
+    pub fn register(mut self, name: &'static str, implementation: F) -> Self {
+        self.entries.push((name, implementation));
+        self
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|(name, _)| *name).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<F> {
+        self.entries
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, implementation)| *implementation)
+    }
+
+    /// Picks the implementation requested on the command line, falling back
+    /// to the default when none was requested.
+    pub fn select(&self, name: Option<&str>) -> Result<F, String> {
+        match name {
+            None => Ok(self.entries[0].1),
+            Some(name) => self.get(name).ok_or_else(|| {
+                format!(
+                    "Unknown implementation '{}', expected one of: {}",
+                    name,
+                    self.names().join(", ")
+                )
+            }),
+        }
+    }
+
+    /// Runs `call` against every implementation and returns the results.
+    pub fn run_all<O>(&self, call: impl Fn(F) -> O) -> Vec<(&'static str, O)> {
+        self.entries
+            .iter()
+            .map(|(name, implementation)| (*name, call(*implementation)))
+            .collect()
+    }
+
+    /// Panics unless every implementation returns the same result as the
+    /// default one.
+    pub fn assert_agree<O>(&self, call: impl Fn(F) -> O)
+    where
+        O: PartialEq + Debug,
+    {
+        let mut results = self.run_all(call).into_iter();
+        let (reference_name, reference) = results.next().unwrap();
+
+        for (name, result) in results {
+            assert_eq!(
+                reference, result,
+                "implementations '{}' and '{}' disagree",
+                reference_name, name
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn double(n: u32) -> u32 {
+        n * 2
+    }
+
+    fn shift(n: u32) -> u32 {
+        n << 1
+    }
+
+    fn square(n: u32) -> u32 {
+        n * n
+    }
+
+    #[test]
+    fn select() {
+        let impls =
+            Implementations::new("double", double as fn(u32) -> u32).register("shift", shift);
+
+        assert_eq!(impls.names(), vec!["double", "shift"]);
+        assert_eq!(impls.select(None).map(|f| f(3)), Ok(6));
+        assert_eq!(impls.select(Some("shift")).map(|f| f(4)), Ok(8));
+        assert!(impls.select(Some("square")).is_err());
+    }
+
+    #[test]
+    fn agree() {
+        let impls =
+            Implementations::new("double", double as fn(u32) -> u32).register("shift", shift);
+        impls.assert_agree(|f| f(21));
+    }
+
+    #[test]
+    #[should_panic(expected = "implementations 'double' and 'square' disagree")]
+    fn disagree() {
+        let impls =
+            Implementations::new("double", double as fn(u32) -> u32).register("square", square);
+        impls.assert_agree(|f| f(3));
+    }
+}